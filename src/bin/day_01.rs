@@ -1,60 +1,151 @@
+use aoc2023::{input, util::TokenScanner};
 use log::debug;
-use regex::Regex;
 
 aoc2023::solver!(part1, part2);
 
+const WORDS: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
 fn part1(lines: &[String]) -> String {
-    let re_first = Regex::new(r"^[^\d]*(\d)").unwrap();
-    let re_last = Regex::new(r".*(\d)[^\d]*$").unwrap();
+    let scanner = TokenScanner::new(digit_tokens());
+    let total = calibration_total(lines, &scanner);
 
-    let mut total = 0;
-    for l in lines {
-        let first_digit = re_first.captures(l).unwrap();
-        let last_digit = re_last.captures(l).unwrap();
+    format!("{}", total)
+}
 
-        let digits = format!("{}{}", &first_digit[1], &last_digit[1]);
-        let numeric = digits.parse::<i32>().unwrap();
+// With --words <file>, digits can also be spelled as in the file, one
+// `word=value` per line.
+fn part2(lines: &[String]) -> String {
+    let mut tokens = digit_tokens();
+    tokens.extend(
+        WORDS
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.to_string(), i as u32)),
+    );
 
-        total += numeric;
+    if let Some(path) = input::option::<String>("words") {
+        tokens.extend(parse_words(&input::read_lines(&path)));
     }
 
+    let scanner = TokenScanner::new(tokens);
+    let total = calibration_total(lines, &scanner);
+
     format!("{}", total)
 }
 
-fn part2(lines: &[String]) -> String {
-    let re = Regex::new(r"(\d|one|two|three|four|five|six|seven|eight|nine|zero)").unwrap();
-
-    let mut total = 0;
-    for l in lines {
-        let mut digits: Vec<i32> = vec![];
-        let mut index = 0;
-        while let Some(m) = re.find_at(l, index) {
-            index = m.start() + 1;
-            let capture = m.as_str();
-            let value = match capture {
-                "one" => 1,
-                "two" => 2,
-                "three" => 3,
-                "four" => 4,
-                "five" => 5,
-                "six" => 6,
-                "seven" => 7,
-                "eight" => 8,
-                "nine" => 9,
-                "zero" => 0,
-                _ => capture.parse::<i32>().unwrap(),
+fn parse_words(lines: &[String]) -> Vec<(String, u32)> {
+    lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            let (word, value) = l
+                .split_once('=')
+                .unwrap_or_else(|| panic!("Invalid word: {}", l));
+            let value = value
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid word value: {}", l));
+            (word.trim().to_string(), value)
+        })
+        .collect()
+}
+
+fn digit_tokens() -> Vec<(String, u32)> {
+    (0..10).map(|d| (d.to_string(), d)).collect()
+}
+
+// Sum of the two digit numbers made from the first and last digit on each
+// line. Digits may overlap, as in "eightwo".
+fn calibration_total(lines: &[String], scanner: &TokenScanner<u32>) -> u32 {
+    lines
+        .iter()
+        .map(|l| {
+            let digits = scanner.find_all(l);
+            let (Some(first), Some(last)) = (
+                digits.first().map(|m| *m.value),
+                digits.last().map(|m| *m.value),
+            ) else {
+                panic!("No digits found in line: {}", l);
             };
 
-            digits.push(value);
-        }
+            debug!("{}{}", first, last);
 
-        let first_digit = digits.first().unwrap();
-        let second_digit = digits.last().unwrap();
+            10 * first + last
+        })
+        .sum()
+}
 
-        debug!("{}{}", first_digit, second_digit);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        total += (10 * first_digit) + second_digit;
+    fn to_lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
     }
 
-    format!("{}", total)
+    fn word_scanner() -> TokenScanner<u32> {
+        let mut tokens = digit_tokens();
+        tokens.extend(
+            WORDS
+                .iter()
+                .enumerate()
+                .map(|(i, w)| (w.to_string(), i as u32)),
+        );
+        TokenScanner::new(tokens)
+    }
+
+    #[test]
+    fn test_example() {
+        let lines = to_lines(&["1abc2", "pqr3stu8vwx", "a1b2c3d4e5f", "treb7uchet"]);
+        assert_eq!(part1(&lines), "142");
+
+        let lines = to_lines(&[
+            "two1nine",
+            "eightwothree",
+            "abcone2threexyz",
+            "xtwone3four",
+            "4nineeightseven2",
+            "zoneight234",
+            "7pqrstsixteen",
+        ]);
+        assert_eq!(part2(&lines), "281");
+    }
+
+    #[test]
+    fn test_overlapping_words() {
+        let scanner = word_scanner();
+
+        assert_eq!(calibration_total(&to_lines(&["oneight"]), &scanner), 18);
+        assert_eq!(calibration_total(&to_lines(&["eightwo"]), &scanner), 82);
+        assert_eq!(calibration_total(&to_lines(&["twone"]), &scanner), 21);
+        assert_eq!(calibration_total(&to_lines(&["sevenine"]), &scanner), 79);
+    }
+
+    #[test]
+    fn test_custom_words() {
+        let words = parse_words(&to_lines(&["eins=1", "", "zwei = 2", "drei=3"]));
+        assert_eq!(
+            words,
+            vec![
+                ("eins".to_string(), 1),
+                ("zwei".to_string(), 2),
+                ("drei".to_string(), 3)
+            ]
+        );
+
+        let mut tokens = digit_tokens();
+        tokens.extend(words);
+        let scanner = TokenScanner::new(tokens);
+
+        assert_eq!(calibration_total(&to_lines(&["xeinsdreix"]), &scanner), 13);
+        assert_eq!(calibration_total(&to_lines(&["drei7one"]), &scanner), 37);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid word value: eins=one")]
+    fn test_invalid_word_value() {
+        parse_words(&to_lines(&["eins=one"]));
+    }
 }
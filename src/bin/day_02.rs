@@ -1,77 +1,283 @@
-use aoc2023::util::get_first_number;
+use std::collections::{BTreeMap, BTreeSet};
+
+use aoc2023::{input, util::get_first_number};
 
 aoc2023::solver!(part1, part2);
 
-#[derive(Default, Debug)]
-struct Round {
-    red: i32,
-    green: i32,
-    blue: i32,
-}
+// Cubes of each colour, by colour name.
+type Bag = BTreeMap<String, u32>;
 
+// Options:
+//   --query <constraints>  games whose minimum bag meets every constraint,
+//                          e.g. red<=12,green<=13 or yellow>0
+//   --output bags          minimum bag for every game
 fn part1(lines: &[String]) -> String {
-    let max_red = 12;
-    let max_green = 13;
-    let max_blue = 14;
+    let games: Vec<_> = lines.iter().map(|l| Game::parse(l)).collect();
+    if let Some(result) = run_options(&games) {
+        return result;
+    }
+
+    let query = Query::parse("red<=12,green<=13,blue<=14");
+    let total: u32 = games
+        .iter()
+        .filter(|g| query.matches(&g.minimum_bag()))
+        .map(|g| g.id)
+        .sum();
+
+    format!("{}", total)
+}
 
-    let mut total = 0;
+fn part2(lines: &[String]) -> String {
+    let games: Vec<_> = lines.iter().map(|l| Game::parse(l)).collect();
+    if let Some(result) = run_options(&games) {
+        return result;
+    }
 
-    for line in lines {
-        let (game, spec) = line.split_once(":").unwrap();
+    // A colour missing from a game's bag counts as zero cubes.
+    let colours: BTreeSet<_> = games.iter().flat_map(|g| g.colours()).collect();
+    let total: u32 = games
+        .iter()
+        .map(|g| {
+            let bag = g.minimum_bag();
+            colours
+                .iter()
+                .map(|c| bag.get(*c).copied().unwrap_or(0))
+                .product::<u32>()
+        })
+        .sum();
 
-        let game_number: i32 = get_first_number(game);
+    format!("{}", total)
+}
 
-        let mut rounds = spec.split(';');
-        let invalid = rounds.any(|r| {
-            let round = parse_round(r);
-            return round.red > max_red || round.green > max_green || round.blue > max_blue;
-        });
+fn run_options(games: &[Game]) -> Option<String> {
+    if let Some(q) = input::option::<String>("query") {
+        let query = Query::parse(&q);
+        let matching: Vec<_> = games
+            .iter()
+            .filter(|g| query.matches(&g.minimum_bag()))
+            .collect();
 
-        if !invalid {
-            total += game_number
-        }
+        let mut rows: Vec<_> = matching
+            .iter()
+            .map(|g| format!("Game {}: {}", g.id, format_bag(&g.minimum_bag())))
+            .collect();
+        rows.push(format!(
+            "{} games match, id total {}",
+            matching.len(),
+            matching.iter().map(|g| g.id).sum::<u32>()
+        ));
+        return Some(rows.join("\n"));
     }
 
-    format!("{}", total)
+    match input::option::<String>("output").as_deref() {
+        Some("bags") => {
+            let rows: Vec<_> = games
+                .iter()
+                .map(|g| format!("Game {}: {}", g.id, format_bag(&g.minimum_bag())))
+                .collect();
+            Some(rows.join("\n"))
+        }
+        Some(o) => panic!("Unknown output: {}", o),
+        None => None,
+    }
 }
 
-fn part2(lines: &[String]) -> String {
-    let mut total = 0;
+fn format_bag(bag: &Bag) -> String {
+    let counts: Vec<_> = bag.iter().map(|(c, n)| format!("{}={}", c, n)).collect();
+    counts.join(",")
+}
 
-    for line in lines {
-        let (_, spec) = line.split_once(":").unwrap();
+#[derive(Debug, PartialEq, Eq)]
+struct Game {
+    id: u32,
+    rounds: Vec<Bag>,
+}
 
-        let rounds = spec.split(';');
-        let mut max_round = Round::default();
+impl Game {
+    // e.g. "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"
+    fn parse(line: &str) -> Self {
+        let (game, spec) = line
+            .split_once(':')
+            .unwrap_or_else(|| panic!("Invalid game: {}", line));
 
-        for r in rounds {
-            let round = parse_round(r);
-            max_round.red = std::cmp::max(max_round.red, round.red);
-            max_round.green = std::cmp::max(max_round.green, round.green);
-            max_round.blue = std::cmp::max(max_round.blue, round.blue);
+        Self {
+            id: get_first_number(game),
+            rounds: spec.split(';').map(parse_round).collect(),
         }
+    }
 
-        let power = max_round.red * max_round.blue * max_round.green;
-        total += power
+    fn colours(&self) -> impl Iterator<Item = &str> {
+        self.rounds
+            .iter()
+            .flat_map(|r| r.keys().map(|c| c.as_str()))
     }
 
-    format!("{}", total)
+    // Fewest cubes of each colour that make every round possible.
+    fn minimum_bag(&self) -> Bag {
+        let mut bag = Bag::new();
+        for (colour, count) in self.rounds.iter().flatten() {
+            let entry = bag.entry(colour.clone()).or_insert(0);
+            *entry = (*entry).max(*count);
+        }
+        bag
+    }
 }
 
-fn parse_round(source: &str) -> Round {
-    let mut round: Round = Round::default();
+fn parse_round(source: &str) -> Bag {
+    let mut round = Bag::new();
 
     for part in source.split(',').map(|s| s.trim()) {
-        let (number, color) = part.split_once(' ').unwrap();
-        let value = number.parse::<i32>().unwrap();
-
-        match color {
-            "red" => round.red = value,
-            "green" => round.green = value,
-            "blue" => round.blue = value,
-            _ => panic!("Unexpected color {}", color),
-        }
+        let (number, colour) = part
+            .split_once(' ')
+            .unwrap_or_else(|| panic!("Invalid cube count: {}", part));
+        let value = number.parse::<u32>().unwrap();
+
+        *round.entry(colour.to_string()).or_insert(0) += value;
     }
 
     round
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Constraint {
+    colour: String,
+    op: Op,
+    value: u32,
+}
+
+// Comma separated constraints on the number of cubes of a colour, all of
+// which must hold. A colour not in the bag has zero cubes.
+#[derive(Debug, PartialEq, Eq)]
+struct Query {
+    constraints: Vec<Constraint>,
+}
+
+impl Query {
+    fn parse(spec: &str) -> Self {
+        let constraints = spec
+            .split(',')
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty())
+            .map(|c| {
+                let Some(pos) = c.find(['<', '>', '=']) else {
+                    panic!("Invalid constraint: {}", c);
+                };
+                let (colour, rest) = c.split_at(pos);
+
+                // Longest operators first, so <= is not read as <.
+                let (op, value) = [
+                    ("<=", Op::LessOrEqual),
+                    (">=", Op::GreaterOrEqual),
+                    ("<", Op::Less),
+                    (">", Op::Greater),
+                    ("=", Op::Equal),
+                ]
+                .iter()
+                .find_map(|(s, op)| rest.strip_prefix(s).map(|v| (*op, v)))
+                .unwrap();
+
+                Constraint {
+                    colour: colour.trim().to_string(),
+                    op,
+                    value: value
+                        .trim()
+                        .parse()
+                        .unwrap_or_else(|_| panic!("Invalid constraint value: {}", c)),
+                }
+            })
+            .collect();
+
+        Self { constraints }
+    }
+
+    fn matches(&self, bag: &Bag) -> bool {
+        self.constraints.iter().all(|c| {
+            let actual = bag.get(&c.colour).copied().unwrap_or(0);
+            match c.op {
+                Op::Less => actual < c.value,
+                Op::LessOrEqual => actual <= c.value,
+                Op::Equal => actual == c.value,
+                Op::GreaterOrEqual => actual >= c.value,
+                Op::Greater => actual > c.value,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        [
+            "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green",
+            "Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue",
+            "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+            "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red",
+            "Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(part1(&example()), "8");
+        assert_eq!(part2(&example()), "2286");
+    }
+
+    #[test]
+    fn test_minimum_bag() {
+        let game = Game::parse("Game 7: 2 teal, 1 red; 5 teal; 3 red, 1 mauve");
+
+        assert_eq!(game.id, 7);
+        assert_eq!(format_bag(&game.minimum_bag()), "mauve=1,red=3,teal=5");
+    }
+
+    #[test]
+    fn test_query_parse() {
+        let query = Query::parse("red<=12, green>3,blue=0");
+
+        assert_eq!(
+            query.constraints,
+            vec![
+                Constraint {
+                    colour: "red".to_string(),
+                    op: Op::LessOrEqual,
+                    value: 12
+                },
+                Constraint {
+                    colour: "green".to_string(),
+                    op: Op::Greater,
+                    value: 3
+                },
+                Constraint {
+                    colour: "blue".to_string(),
+                    op: Op::Equal,
+                    value: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query_matches() {
+        let bag = Game::parse("Game 1: 3 blue, 4 red; 2 green").minimum_bag();
+
+        assert!(Query::parse("red<=4,blue>=3").matches(&bag));
+        assert!(!Query::parse("red<4").matches(&bag));
+        assert!(Query::parse("yellow=0").matches(&bag));
+        assert!(!Query::parse("yellow>0").matches(&bag));
+        assert!(Query::parse("").matches(&bag));
+    }
+}
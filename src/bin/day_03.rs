@@ -1,128 +1,239 @@
-use log::info;
-use regex::Regex;
 use std::collections::HashMap;
-use std::collections::HashSet;
-
-aoc2023::solver!(part1, part2);
 
-#[derive(Debug, Default, Eq, PartialEq, Hash)]
-struct Point(i32, i32);
+use aoc2023::{collections::grid::Grid, input};
+use log::info;
 
-type GearMap = HashMap<Point, Vec<i32>>;
+aoc2023::solver!(part1, part2);
 
+// Use --symbol <c> to only count numbers next to that symbol.
 fn part1(lines: &[String]) -> String {
-    let symbols = get_symbol_points(lines);
-    let re_digits = Regex::new(r"(\d+)").unwrap();
-
-    let mut total = 0;
-    for (x, line) in lines.iter().enumerate() {
-        for m in re_digits.find_iter(line) {
-            let mut to_check: Vec<Point> = Vec::new();
-
-            let value = m.as_str().parse::<i32>().unwrap();
-
-            let current_line: i32 = x.try_into().unwrap();
-            let start: i32 = m.start().try_into().unwrap();
-            let end: i32 = m.end().try_into().unwrap();
-
-            // Same line
-            to_check.push(Point(current_line, start - 1));
-            to_check.push(Point(current_line, end));
-
-            // Previous and next lines
-            for pos in start - 1..=end {
-                to_check.push(Point(current_line - 1, pos));
-                to_check.push(Point(current_line + 1, pos));
-            }
+    let schematic = Schematic::build(&parse_grid(lines));
 
-            let has_symbol = to_check.iter().any(|p| symbols.contains(p));
-            if has_symbol {
-                total += value;
-            }
-        }
-    }
+    let total: u32 = match input::option::<char>("symbol") {
+        Some(symbol) => schematic
+            .numbers_adjacent_to(symbol)
+            .iter()
+            .map(|n| n.value)
+            .sum(),
+        None => schematic.part_numbers().map(|n| n.value).sum(),
+    };
 
     format!("{}", total)
 }
 
+// Use --symbol <c> and --neighbours <n> to change what counts as a gear, the
+// ratio is the product of all the neighbouring numbers.
 fn part2(lines: &[String]) -> String {
-    let mut gears = get_possible_gears(lines);
-    let re_digits = Regex::new(r"(\d+)").unwrap();
+    let schematic = Schematic::build(&parse_grid(lines));
+    let symbol = input::option("symbol").unwrap_or('*');
+    let neighbours = input::option("neighbours").unwrap_or(2);
+
+    let total: u64 = schematic
+        .symbols_with_neighbours(symbol, neighbours)
+        .iter()
+        .map(|s| {
+            let values: Vec<_> = s
+                .numbers
+                .iter()
+                .map(|n| schematic.numbers[*n].value)
+                .collect();
+            info!("Gear at {:?} has neighbours {:?}", s.position, values);
+            values.iter().map(|v| *v as u64).product::<u64>()
+        })
+        .sum();
 
-    for (x, line) in lines.iter().enumerate() {
-        for m in re_digits.find_iter(line) {
-            let mut to_check: Vec<Point> = Vec::new();
+    format!("{}", total)
+}
 
-            let value = m.as_str().parse::<i32>().unwrap();
+fn parse_grid(lines: &[String]) -> Grid<char> {
+    lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.chars())
+        .collect()
+}
 
-            let current_line: i32 = x.try_into().unwrap();
-            let start: i32 = m.start().try_into().unwrap();
-            let end: i32 = m.end().try_into().unwrap();
+#[derive(Debug, PartialEq, Eq)]
+struct NumberSpan {
+    value: u32,
+    row: usize,
+    // Columns start..end hold the digits.
+    start: usize,
+    end: usize,
+    // Indices into Schematic::symbols of every adjacent symbol.
+    symbols: Vec<usize>,
+}
 
-            // Same line
-            to_check.push(Point(current_line, start - 1));
-            to_check.push(Point(current_line, end));
+#[derive(Debug, PartialEq, Eq)]
+struct Symbol {
+    symbol: char,
+    position: (usize, usize),
+    // Indices into Schematic::numbers of every adjacent number.
+    numbers: Vec<usize>,
+}
 
-            // Previous and next lines
-            for pos in start - 1..=end {
-                to_check.push(Point(current_line - 1, pos));
-                to_check.push(Point(current_line + 1, pos));
+// Every number and symbol in the schematic, linked to each other when they
+// are adjacent, including diagonally.
+#[derive(Debug)]
+struct Schematic {
+    numbers: Vec<NumberSpan>,
+    symbols: Vec<Symbol>,
+}
+
+impl Schematic {
+    fn build(grid: &Grid<char>) -> Self {
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        let mut symbol_index = HashMap::new();
+
+        for (i, j, c) in grid.enumerate() {
+            if *c != '.' && !c.is_ascii_digit() {
+                symbol_index.insert((i, j), symbols.len());
+                symbols.push(Symbol {
+                    symbol: *c,
+                    position: (i, j),
+                    numbers: Vec::new(),
+                });
             }
+        }
 
-            for p in to_check {
-                if gears.contains_key(&p) {
-                    gears.get_mut(&p).unwrap().push(value);
+        for row in 0..grid.rows() {
+            let mut col = 0;
+            while col < grid.cols() {
+                if !grid.get(row, col).is_ascii_digit() {
+                    col += 1;
+                    continue;
                 }
+
+                let start = col;
+                let mut value = 0;
+                while col < grid.cols() && grid.get(row, col).is_ascii_digit() {
+                    value = value * 10 + grid.get(row, col).to_digit(10).unwrap();
+                    col += 1;
+                }
+
+                let id = numbers.len();
+                let mut adjacent = Vec::new();
+                for r in row.saturating_sub(1)..(row + 2).min(grid.rows()) {
+                    for c in start.saturating_sub(1)..(col + 1).min(grid.cols()) {
+                        if let Some(s) = symbol_index.get(&(r, c)) {
+                            adjacent.push(*s);
+                            symbols[*s].numbers.push(id);
+                        }
+                    }
+                }
+
+                numbers.push(NumberSpan {
+                    value,
+                    row,
+                    start,
+                    end: col,
+                    symbols: adjacent,
+                });
             }
         }
-    }
 
-    let mut total = 0;
-    for (gear, neighbors) in gears {
-        info!("Looking at gear {:?}", gear);
-        if neighbors.len() == 2 {
-            info!("Has neighbors {} and {}", neighbors[0], neighbors[1]);
-            let ratio = neighbors[0] * neighbors[1];
-            total += ratio
-        }
+        Self { numbers, symbols }
     }
 
-    format!("{}", total)
-}
+    // Numbers next to any symbol.
+    fn part_numbers(&self) -> impl Iterator<Item = &NumberSpan> {
+        self.numbers.iter().filter(|n| !n.symbols.is_empty())
+    }
 
-fn get_symbol_points(lines: &[String]) -> HashSet<Point> {
-    let mut result: HashSet<Point> = HashSet::new();
-
-    for (x, line) in lines.iter().enumerate() {
-        for (y, c) in line.chars().enumerate() {
-            match c {
-                '.' => (),
-                '0'..='9' => (),
-                _ => {
-                    let point = Point(x.try_into().unwrap(), y.try_into().unwrap());
-                    result.insert(point);
-                }
-            };
-        }
+    fn numbers_adjacent_to(&self, symbol: char) -> Vec<&NumberSpan> {
+        self.numbers
+            .iter()
+            .filter(|n| n.symbols.iter().any(|s| self.symbols[*s].symbol == symbol))
+            .collect()
     }
 
-    result
+    fn symbols_with_neighbours(&self, symbol: char, count: usize) -> Vec<&Symbol> {
+        self.symbols
+            .iter()
+            .filter(|s| s.symbol == symbol && s.numbers.len() == count)
+            .collect()
+    }
 }
 
-fn get_possible_gears(lines: &[String]) -> GearMap {
-    let mut result = GearMap::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        [
+            "467..114..",
+            "...*......",
+            "..35..633.",
+            "......#...",
+            "617*......",
+            ".....+.58.",
+            "..592.....",
+            "......755.",
+            "...$.*....",
+            ".664.598..",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+    }
 
-    for (x, line) in lines.iter().enumerate() {
-        for (y, c) in line.chars().enumerate() {
-            match c {
-                '*' => {
-                    let point = Point(x.try_into().unwrap(), y.try_into().unwrap());
-                    result.insert(point, Vec::new());
-                }
-                _ => (),
+    #[test]
+    fn test_example() {
+        assert_eq!(part1(&example()), "4361");
+        assert_eq!(part2(&example()), "467835");
+    }
+
+    #[test]
+    fn test_spans() {
+        let schematic = Schematic::build(&parse_grid(&example()));
+
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(
+            schematic.numbers[1],
+            NumberSpan {
+                value: 114,
+                row: 0,
+                start: 5,
+                end: 8,
+                symbols: vec![]
             }
-        }
+        );
+        assert_eq!(schematic.numbers[6].value, 592);
+        assert_eq!(
+            schematic.symbols[schematic.numbers[6].symbols[0]].symbol,
+            '+'
+        );
     }
 
-    result
+    #[test]
+    fn test_queries() {
+        let schematic = Schematic::build(&parse_grid(&example()));
+
+        let values =
+            |numbers: Vec<&NumberSpan>| numbers.iter().map(|n| n.value).collect::<Vec<_>>();
+        assert_eq!(values(schematic.numbers_adjacent_to('#')), vec![633]);
+        assert_eq!(values(schematic.numbers_adjacent_to('$')), vec![664]);
+
+        let single: Vec<_> = schematic
+            .symbols_with_neighbours('*', 1)
+            .iter()
+            .map(|s| s.position)
+            .collect();
+        assert_eq!(single, vec![(4, 3)]);
+    }
+
+    #[test]
+    fn test_number_at_edge() {
+        let lines: Vec<_> = ["..12", "1*..", "..*3"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        let schematic = Schematic::build(&parse_grid(&lines));
+
+        let values: Vec<_> = schematic.part_numbers().map(|n| n.value).collect();
+        assert_eq!(values, vec![12, 1, 3]);
+        assert_eq!(schematic.symbols[0].numbers, vec![0, 1]);
+        assert_eq!(schematic.symbols[1].numbers, vec![2]);
+    }
 }
@@ -1,49 +1,254 @@
-use aoc2023::util::{self, get_first_number};
-use std::collections::HashSet;
+use aoc2023::{input, util::get_all_numbers};
+use num::{BigUint, One, Zero};
+use std::{collections::HashSet, fmt::Display};
 
 aoc2023::solver!(part1, part2);
 
+// Options for rule variants:
+//   --points <formula>     doubling (default), linear or square
+//   --copies <direction>   forward (default) or backward
+//   --cap <n>              no card can have more than n instances
+//   --output table         print each card's matches, points and copies
 fn part1(lines: &[String]) -> String {
-    let mut total = 0;
-    for line in lines {
-        let (_, card) = line.split_once(':').unwrap();
+    let cards: Vec<_> = lines.iter().map(|l| Card::parse(l)).collect();
+    let points = points_option();
 
-        let winners = count_winning_numbers(card);
-        if winners > 0 {
-            total += 2_i32.pow((winners - 1).try_into().unwrap());
+    let total: BigUint = cards.iter().map(|c| points.score(c.matches())).sum();
+
+    output(&cards, total)
+}
+
+fn part2(lines: &[String]) -> String {
+    let cards: Vec<_> = lines.iter().map(|l| Card::parse(l)).collect();
+    let total: u64 = copy_counts(&cards, &copy_rules_option()).iter().sum();
+
+    output(&cards, total)
+}
+
+fn points_option() -> Points {
+    Points::parse(&input::option::<String>("points").unwrap_or("doubling".to_string()))
+}
+
+fn copy_rules_option() -> CopyRules {
+    CopyRules {
+        direction: Direction::parse(
+            &input::option::<String>("copies").unwrap_or("forward".to_string()),
+        ),
+        cap: input::option("cap"),
+    }
+}
+
+fn output<T: Display>(cards: &[Card], total: T) -> String {
+    match input::option::<String>("output").as_deref() {
+        Some("table") => {
+            let points = points_option();
+            let copies = copy_counts(cards, &copy_rules_option());
+
+            let mut rows = vec!["card matches points copies".to_string()];
+            rows.extend(cards.iter().zip(copies).map(|(c, n)| {
+                format!(
+                    "{:>4} {:>7} {:>6} {:>6}",
+                    c.id,
+                    c.matches(),
+                    points.score(c.matches()),
+                    n
+                )
+            }));
+            rows.push(format!("Total {}", total));
+            rows.join("\n")
         }
+        Some(o) => panic!("Unknown output: {}", o),
+        None => format!("{}", total),
     }
+}
 
-    format!("{}", total)
+#[derive(Debug, PartialEq, Eq)]
+struct Card {
+    id: usize,
+    winners: Vec<u32>,
+    owned: Vec<u32>,
 }
 
-fn part2(lines: &[String]) -> String {
-    let mut counts: Vec<_> = lines.iter().map(|_| 1).collect();
-    for line in lines {
-        let (card, values) = line.split_once(':').unwrap();
-        let card_num: usize = get_first_number(card);
-        let count = count_winning_numbers(values) as usize;
-
-        let copies = counts[card_num - 1];
-        for i in card_num..card_num + count {
-            counts[i] += copies
+impl Card {
+    // e.g. "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53"
+    fn parse(line: &str) -> Self {
+        let (card, values) = line
+            .split_once(':')
+            .unwrap_or_else(|| panic!("Invalid card: {}", line));
+        let (winners, owned) = values
+            .split_once('|')
+            .unwrap_or_else(|| panic!("Invalid card: {}", line));
+
+        Self {
+            id: get_all_numbers(card)[0],
+            winners: get_all_numbers(winners),
+            owned: get_all_numbers(owned),
         }
     }
 
-    let total: i32 = counts.iter().sum();
+    // Number of distinct owned numbers that are winners.
+    fn matches(&self) -> usize {
+        let winners: HashSet<_> = self.winners.iter().collect();
+        let owned: HashSet<_> = self.owned.iter().collect();
+        winners.intersection(&owned).count()
+    }
+}
 
-    format!("{}", total)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Points {
+    // 1 for the first match, doubled for each one after.
+    Doubling,
+    // 1 for each match.
+    Linear,
+    // Square of the number of matches.
+    Square,
 }
 
-fn count_winning_numbers(card: &str) -> i32 {
-    let (winning, have) = card.split_once('|').unwrap();
+impl Points {
+    fn parse(name: &str) -> Self {
+        match name {
+            "doubling" => Points::Doubling,
+            "linear" => Points::Linear,
+            "square" => Points::Square,
+            _ => panic!("Unknown points formula: {}", name),
+        }
+    }
+
+    // Doubling passes u64 once a card has more than 64 matches.
+    fn score(&self, matches: usize) -> BigUint {
+        match self {
+            Points::Doubling if matches == 0 => BigUint::zero(),
+            Points::Doubling => BigUint::one() << (matches - 1),
+            Points::Linear => BigUint::from(matches),
+            Points::Square => BigUint::from(matches) * matches,
+        }
+    }
+}
 
-    let winning_set: HashSet<i32> = HashSet::from_iter(util::get_all_numbers(winning));
-    let have_set: HashSet<i32> = HashSet::from_iter(util::get_all_numbers(have));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    // Each match wins a copy of one of the following cards.
+    Forward,
+    // Each match wins a copy of one of the preceding cards.
+    Backward,
+}
 
-    winning_set
-        .intersection(&have_set)
-        .count()
-        .try_into()
-        .unwrap()
+impl Direction {
+    fn parse(name: &str) -> Self {
+        match name {
+            "forward" => Direction::Forward,
+            "backward" => Direction::Backward,
+            _ => panic!("Unknown copy direction: {}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CopyRules {
+    direction: Direction,
+    cap: Option<u64>,
+}
+
+// Instances of each card held once every copy has been won, in card order.
+// Cards are processed in the direction copies are won, so a card's count is
+// final before it hands out copies. Copies past the end are not won.
+fn copy_counts(cards: &[Card], rules: &CopyRules) -> Vec<u64> {
+    let mut counts = vec![1; cards.len()];
+    let order: Vec<_> = match rules.direction {
+        Direction::Forward => (0..cards.len()).collect(),
+        Direction::Backward => (0..cards.len()).rev().collect(),
+    };
+
+    for (pos, i) in order.iter().enumerate() {
+        if let Some(cap) = rules.cap {
+            counts[*i] = counts[*i].min(cap);
+        }
+
+        let copies = counts[*i];
+        let won = cards[*i].matches();
+        for j in order.iter().skip(pos + 1).take(won) {
+            counts[*j] += copies;
+        }
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Card> {
+        [
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53",
+            "Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19",
+            "Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1",
+            "Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83",
+            "Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36",
+            "Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11",
+        ]
+        .iter()
+        .map(|l| Card::parse(l))
+        .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let card = Card::parse("Card 12: 1 2 | 3  4 5");
+        assert_eq!(card.id, 12);
+        assert_eq!(card.winners, vec![1, 2]);
+        assert_eq!(card.owned, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_points() {
+        let matches: Vec<_> = example().iter().map(|c| c.matches()).collect();
+        assert_eq!(matches, vec![4, 2, 2, 1, 0, 0]);
+
+        let score = |p: Points| matches.iter().map(|m| p.score(*m)).sum::<BigUint>();
+        assert_eq!(score(Points::Doubling), BigUint::from(13u32));
+        assert_eq!(score(Points::Linear), BigUint::from(9u32));
+        assert_eq!(score(Points::Square), BigUint::from(25u32));
+    }
+
+    #[test]
+    fn test_doubling_past_u64() {
+        assert_eq!(Points::Doubling.score(64), BigUint::from(1u64 << 63));
+        assert_eq!(Points::Doubling.score(100), BigUint::one() << 99u32);
+
+        let winners: Vec<_> = (1..=100).map(|n| n.to_string()).collect();
+        let line = format!("Card 1: {} | {}", winners.join(" "), winners.join(" "));
+        assert_eq!(Card::parse(&line).matches(), 100);
+        assert_eq!(part1(&[line]), (BigUint::one() << 99u32).to_string());
+    }
+
+    #[test]
+    fn test_copies_forward() {
+        let rules = CopyRules {
+            direction: Direction::Forward,
+            cap: None,
+        };
+        assert_eq!(copy_counts(&example(), &rules), vec![1, 2, 4, 8, 14, 1]);
+    }
+
+    #[test]
+    fn test_copies_capped() {
+        let rules = CopyRules {
+            direction: Direction::Forward,
+            cap: Some(3),
+        };
+        assert_eq!(copy_counts(&example(), &rules), vec![1, 2, 3, 3, 3, 1]);
+    }
+
+    #[test]
+    fn test_copies_backward() {
+        let rules = CopyRules {
+            direction: Direction::Backward,
+            cap: None,
+        };
+        // Card 4 wins a copy of card 3, both copies of card 3 win cards 2
+        // and 1, then all three copies of card 2 win card 1. There is no card
+        // before card 1 for its second match.
+        assert_eq!(copy_counts(&example(), &rules), vec![6, 3, 2, 1, 1, 1]);
+    }
 }
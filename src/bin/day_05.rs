@@ -1,6 +1,10 @@
 use std::{collections::VecDeque, ops::Range};
 
-use aoc2023::util::get_all_numbers;
+use aoc2023::{
+    input,
+    solver::is_parallel,
+    util::{get_all_numbers, maybe_par_map},
+};
 use log::info;
 
 aoc2023::solver!(part1, part2);
@@ -98,6 +102,239 @@ impl MappingGroup {
 
         result
     }
+
+    // The mappings run backwards, with the identity gaps between them made
+    // explicit so that the inverse of a gap value is still itself. Only a
+    // bijection has an inverse, so this is None unless the source ranges
+    // tile exactly the same values as the dest ranges.
+    fn invert(&self) -> Option<MappingGroup> {
+        let sources = self.mappings.iter().map(|m| m.source..m.source + m.count);
+        let dests = self.mappings.iter().map(|m| m.dest..m.dest + m.count);
+
+        let covered = merge_ranges(sources.collect());
+        let total: u64 = self.mappings.iter().map(|m| m.count).sum();
+        if covered != merge_ranges(dests.collect())
+            || covered.iter().map(|r| r.end - r.start).sum::<u64>() != total
+        {
+            return None;
+        }
+
+        let mut mappings: Vec<_> = self
+            .mappings
+            .iter()
+            .map(|m| RangeMapping {
+                source: m.dest,
+                dest: m.source,
+                count: m.count,
+            })
+            .collect();
+
+        let gaps = self
+            .segments()
+            .into_iter()
+            .filter(|s| !covered.iter().any(|r| r.contains(&s.start)))
+            .map(|s| RangeMapping {
+                source: s.start,
+                dest: s.start,
+                count: s.len(),
+            });
+        mappings.extend(gaps);
+
+        Some(MappingGroup { mappings })
+    }
+
+    // Every value below u64::MAX in source order, with the gaps between
+    // mappings filled by identity segments.
+    fn segments(&self) -> Vec<Segment> {
+        let mut mappings: Vec<_> = self.mappings.iter().collect();
+        mappings.sort_by_key(|m| m.source);
+
+        let mut result = Vec::new();
+        let mut cursor = 0;
+        for m in mappings {
+            let end = m.source + m.count;
+            if end <= cursor {
+                continue;
+            }
+
+            if cursor < m.source {
+                result.push(Segment::identity(cursor..m.source));
+                cursor = m.source;
+            }
+
+            result.push(Segment {
+                start: cursor,
+                end,
+                dest: m.dest + (cursor - m.source),
+            });
+            cursor = end;
+        }
+
+        if cursor < u64::MAX {
+            result.push(Segment::identity(cursor..u64::MAX));
+        }
+
+        result
+    }
+}
+
+// Maps start..end onto dest..dest + (end - start).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Segment {
+    start: u64,
+    end: u64,
+    dest: u64,
+}
+
+impl Segment {
+    fn identity(range: Range<u64>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+            dest: range.start,
+        }
+    }
+
+    fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    fn dest_end(&self) -> u64 {
+        self.dest + self.len()
+    }
+
+    fn map(&self, value: u64) -> u64 {
+        self.dest + (value - self.start)
+    }
+}
+
+// All the mapping groups composed into one piecewise-linear function, as a
+// table of segments sorted by source that covers every value below u64::MAX.
+#[derive(Debug)]
+struct Pipeline {
+    segments: Vec<Segment>,
+
+    // Indices into segments sorted by dest, and the furthest dest_end of any
+    // segment up to that point, for finding every segment that can reach a
+    // location even when their images overlap.
+    by_dest: Vec<usize>,
+    reach: Vec<u64>,
+}
+
+impl Pipeline {
+    fn compose(groups: &[MappingGroup]) -> Self {
+        let mut segments = vec![Segment::identity(0..u64::MAX)];
+
+        for group in groups {
+            let next = group.segments();
+            let mut composed: Vec<Segment> = Vec::new();
+
+            for s in &segments {
+                let image = s.dest..s.dest_end();
+                let first = next.partition_point(|n| n.end <= image.start);
+
+                for n in next[first..].iter().take_while(|n| n.start < image.end) {
+                    let start = image.start.max(n.start);
+                    let end = image.end.min(n.end);
+                    let segment = Segment {
+                        start: s.start + (start - image.start),
+                        end: s.start + (end - image.start),
+                        dest: n.map(start),
+                    };
+
+                    match composed.last_mut() {
+                        Some(last)
+                            if last.end == segment.start && last.dest_end() == segment.dest =>
+                        {
+                            last.end = segment.end
+                        }
+                        _ => composed.push(segment),
+                    }
+                }
+            }
+
+            segments = composed;
+        }
+
+        info!(
+            "Composed {} groups into {} segments",
+            groups.len(),
+            segments.len()
+        );
+
+        let mut by_dest: Vec<_> = (0..segments.len()).collect();
+        by_dest.sort_by_key(|i| segments[*i].dest);
+
+        let reach = by_dest
+            .iter()
+            .scan(0, |furthest, i| {
+                *furthest = segments[*i].dest_end().max(*furthest);
+                Some(*furthest)
+            })
+            .collect();
+
+        Self {
+            segments,
+            by_dest,
+            reach,
+        }
+    }
+
+    // Index of the segment containing value, or the first one after it.
+    fn find(&self, value: u64) -> usize {
+        self.segments.partition_point(|s| s.end <= value)
+    }
+
+    fn apply(&self, value: u64) -> u64 {
+        self.segments[self.find(value)].map(value)
+    }
+
+    fn apply_range(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        if range.is_empty() {
+            return Vec::new();
+        }
+
+        self.segments[self.find(range.start)..]
+            .iter()
+            .take_while(|s| s.start < range.end)
+            .map(|s| {
+                let start = range.start.max(s.start);
+                let end = range.end.min(s.end);
+                s.map(start)..s.map(start) + (end - start)
+            })
+            .collect()
+    }
+
+    // Each segment is increasing, so the minimum is at the start of one of
+    // the pieces of the range.
+    fn min_location(&self, range: Range<u64>) -> Option<u64> {
+        self.apply_range(range).iter().map(|r| r.start).min()
+    }
+
+    // Every range of values that maps into locations, in ascending order. A
+    // binary search over by_dest finds the last segment whose image starts
+    // before the end of locations, then the scan walks back only while reach
+    // says an earlier image can still overlap, which for the puzzle's
+    // non-overlapping images is a single segment per piece of locations.
+    fn sources(&self, locations: Range<u64>) -> Vec<Range<u64>> {
+        let candidates = self
+            .by_dest
+            .partition_point(|i| self.segments[*i].dest < locations.end);
+
+        let mut result: Vec<_> = (0..candidates)
+            .rev()
+            .take_while(|k| self.reach[*k] > locations.start)
+            .map(|k| &self.segments[self.by_dest[k]])
+            .filter_map(|s| {
+                let start = locations.start.max(s.dest);
+                let end = locations.end.min(s.dest_end());
+                (start < end).then(|| s.start + (start - s.dest)..s.start + (end - s.dest))
+            })
+            .collect();
+
+        result.sort_by_key(|r| r.start);
+        result
+    }
 }
 
 fn part1(lines: &[String]) -> String {
@@ -109,18 +346,24 @@ fn part1(lines: &[String]) -> String {
 
     let mappings = build_mappings(&mut input);
 
-    let min = values
-        .iter()
-        .map(|v| {
-            let mut mapped_value = *v;
-            for group in &mappings[..] {
-                mapped_value = group.apply(mapped_value);
-            }
-            mapped_value
-        })
+    let seeds: Vec<_> = values.iter().map(|v| *v..*v + 1).collect();
+    reverse_lookup(&mappings, &seeds);
+
+    let pipeline = Pipeline::compose(&mappings);
+    let min = maybe_par_map(&values, is_parallel(), |v| pipeline.apply(*v))
+        .into_iter()
         .min()
         .unwrap();
 
+    // Cross-check the composed table against applying each group in turn.
+    debug_assert_eq!(
+        Some(min),
+        values
+            .iter()
+            .map(|v| mappings.iter().fold(*v, |v, group| group.apply(v)))
+            .min()
+    );
+
     format!("{}", min)
 }
 
@@ -138,15 +381,123 @@ fn part2(lines: &[String]) -> String {
         ranges.push(start..start + count);
     }
 
-    for group in mappings {
-        ranges = group.apply_range(&mut ranges);
+    reverse_lookup(&mappings, &ranges);
+
+    let pipeline = Pipeline::compose(&mappings);
+    let min = ranges
+        .iter()
+        .filter_map(|r| pipeline.min_location(r.clone()))
+        .min()
+        .unwrap();
+
+    // Cross-check the composed table against applying each group in turn.
+    #[cfg(debug_assertions)]
+    {
+        let stepwise = mappings
+            .iter()
+            .fold(ranges.clone(), |mut r, group| group.apply_range(&mut r));
+        assert_eq!(Some(min), stepwise.iter().map(|r| r.start).min());
     }
 
-    let min = ranges.iter().map(|r| r.start).min().unwrap();
+    let seeds: Vec<_> = pipeline
+        .sources(min..min + 1)
+        .into_iter()
+        .map(|s| s.start)
+        .filter(|s| ranges.iter().any(|r| r.contains(s)))
+        .collect();
+    info!("Location {} comes from seeds {:?}", min, seeds);
 
     format!("{}", min)
 }
 
+// With --location <n> or --location <start>..<end>, log every seed that ends
+// up there alongside the answer, one seed or range of seeds per line. This is
+// exact for any almanac, including groups that send several values to the
+// same place or leave some values unreachable.
+fn reverse_lookup(mappings: &[MappingGroup], seeds: &[Range<u64>]) {
+    let Some(location) = input::option::<String>("location") else {
+        return;
+    };
+
+    for line in seeds_for_location(mappings, seeds, &location).lines() {
+        info!("{}", line);
+    }
+}
+
+fn seeds_for_location(mappings: &[MappingGroup], seeds: &[Range<u64>], location: &str) -> String {
+    let locations = parse_range(location);
+    let sources = Pipeline::compose(mappings).sources(locations.clone());
+
+    let found = merge_ranges(
+        sources
+            .iter()
+            .flat_map(|s| {
+                seeds
+                    .iter()
+                    .map(|r| s.start.max(r.start)..s.end.min(r.end))
+                    .filter(|r| !r.is_empty())
+            })
+            .collect(),
+    );
+
+    // Cross-check against running the location back through each group,
+    // which is only possible when every group has an inverse.
+    if let Some(inverses) = mappings
+        .iter()
+        .rev()
+        .map(|g| g.invert())
+        .collect::<Option<Vec<_>>>()
+    {
+        let chained = inverses
+            .iter()
+            .fold(vec![locations], |mut r, group| group.apply_range(&mut r));
+        debug_assert_eq!(merge_ranges(sources), merge_ranges(chained));
+    }
+
+    if found.is_empty() {
+        return format!("No seeds map to location {}", location);
+    }
+
+    let lines: Vec<_> = found
+        .iter()
+        .map(|r| match r.end - r.start {
+            1 => format!("{}", r.start),
+            _ => format!("{}..{}", r.start, r.end),
+        })
+        .collect();
+
+    lines.join("\n")
+}
+
+// Sorted ranges with any that overlap or touch joined together.
+fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.retain(|r| !r.is_empty());
+    ranges.sort_by_key(|r| r.start);
+
+    let mut result: Vec<Range<u64>> = Vec::new();
+    for r in ranges {
+        match result.last_mut() {
+            Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+            _ => result.push(r),
+        }
+    }
+
+    result
+}
+
+fn parse_range(value: &str) -> Range<u64> {
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<u64>()
+            .unwrap_or_else(|_| panic!("Invalid location: {}", value))
+    };
+
+    match value.split_once("..") {
+        Some((start, end)) => parse(start)..parse(end),
+        None => parse(value)..parse(value) + 1,
+    }
+}
+
 fn build_mappings<'a, T>(lines: &mut T) -> Vec<MappingGroup>
 where
     T: Iterator<Item = &'a String>,
@@ -277,4 +628,285 @@ mod tests {
         assert_eq!(1990..2000, result[2]); // Leading portion
         assert_eq!(2100..2110, result[3]); // Trailing portion
     }
+
+    fn create_example() -> Vec<MappingGroup> {
+        let lines: Vec<_> = [
+            "seed-to-soil map:",
+            "50 98 2",
+            "52 50 48",
+            "",
+            "soil-to-fertilizer map:",
+            "0 15 37",
+            "37 52 2",
+            "39 0 15",
+            "",
+            "fertilizer-to-water map:",
+            "49 53 8",
+            "0 11 42",
+            "42 0 7",
+            "57 7 4",
+            "",
+            "water-to-light map:",
+            "88 18 7",
+            "18 25 70",
+            "",
+            "light-to-temperature map:",
+            "45 77 23",
+            "81 45 19",
+            "68 64 13",
+            "",
+            "temperature-to-humidity map:",
+            "0 69 1",
+            "1 0 69",
+            "",
+            "humidity-to-location map:",
+            "60 56 37",
+            "56 93 4",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        build_mappings(&mut lines.iter())
+    }
+
+    #[test]
+    fn test_segments_fill_gaps() {
+        let group = create_test_group();
+        let segments = group[0].segments();
+
+        assert_eq!(Segment::identity(0..75), segments[0]);
+        assert_eq!(
+            Segment {
+                start: 75,
+                end: 100,
+                dest: 10
+            },
+            segments[1]
+        );
+        assert_eq!(Segment::identity(2100..u64::MAX), segments[5]);
+        assert_eq!(6, segments.len());
+    }
+
+    #[test]
+    fn test_compose_matches_stepwise() {
+        let groups = create_example();
+        let pipeline = Pipeline::compose(&groups);
+
+        for seed in 0..200 {
+            let expected = groups.iter().fold(seed, |v, g| g.apply(v));
+            assert_eq!(expected, pipeline.apply(seed));
+            assert_eq!(
+                vec![expected..expected + 1],
+                pipeline.apply_range(seed..seed + 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_compose_min_location() {
+        let groups = create_example();
+        let pipeline = Pipeline::compose(&groups);
+
+        assert_eq!(Some(46), pipeline.min_location(79..93));
+        assert_eq!(None, pipeline.min_location(10..10));
+
+        let expected = (55..68)
+            .map(|s| groups.iter().fold(s, |v, g| g.apply(v)))
+            .min();
+        assert_eq!(expected, pipeline.min_location(55..68));
+    }
+
+    #[test]
+    fn test_compose_sources() {
+        let groups = create_example();
+        let pipeline = Pipeline::compose(&groups);
+
+        assert_eq!(vec![82..83], pipeline.sources(46..47));
+        for seed in 0..200 {
+            let location = groups.iter().fold(seed, |v, g| g.apply(v));
+            let sources = pipeline.sources(location..location + 1);
+            assert!(sources.iter().any(|r| r.contains(&seed)));
+        }
+
+        for source in pipeline.sources(40..60) {
+            for seed in source {
+                let location = groups.iter().fold(seed, |v, g| g.apply(v));
+                assert!((40..60).contains(&location));
+            }
+        }
+    }
+
+    #[test]
+    fn test_compose_sources_overlapping() {
+        // 0..10 and 20..30 both end up on 20..30.
+        let group = create_test_group_from(&["header", "20 0 10"]);
+        let pipeline = Pipeline::compose(&group);
+
+        assert_eq!(vec![5..6, 25..26], pipeline.sources(25..26));
+        assert_eq!(Vec::<Range<u64>>::new(), pipeline.sources(5..6));
+        assert_eq!(vec![0..5, 15..25], pipeline.sources(15..25));
+    }
+
+    fn create_test_group_from(lines: &[&str]) -> Vec<MappingGroup> {
+        let temp: Vec<_> = lines.iter().map(|l| l.to_string()).collect();
+        build_mappings(&mut temp.iter())
+    }
+
+    // The test group with each mapping paired with its reverse, so that it
+    // is a bijection.
+    fn create_inverted_test_group() -> MappingGroup {
+        let group = create_test_group_from(&[
+            "header",
+            "10 75 25",
+            "75 10 25",
+            "200 100 25",
+            "100 200 25",
+            "1000 2000 100",
+            "2000 1000 100",
+        ]);
+        group[0].invert().unwrap()
+    }
+
+    #[test]
+    fn test_invert() {
+        let group = create_inverted_test_group();
+        assert_eq!(99, group.apply(34));
+        assert_eq!(34, group.apply(99));
+        assert_eq!(100, group.apply(200));
+        assert_eq!(300, group.apply(300));
+        assert_eq!(u64::MAX - 1, group.apply(u64::MAX - 1));
+    }
+
+    #[test]
+    fn test_invert_not_bijection() {
+        assert!(create_test_group()[0].invert().is_none());
+        assert!(create_test_group_from(&["header", "20 0 10"])[0]
+            .invert()
+            .is_none());
+        assert!(create_test_group_from(&["header", "0 0 10", "5 5 10"])[0]
+            .invert()
+            .is_none());
+    }
+
+    #[test]
+    fn test_invert_round_trip() {
+        for group in create_example() {
+            let inverted = group.invert().unwrap();
+            for value in 0..200 {
+                assert_eq!(value, inverted.apply(group.apply(value)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invert_range_unmapped() {
+        let group = create_inverted_test_group();
+
+        let mut values = vec![Range { start: 0, end: 10 }];
+        let result = group.apply_range(&mut values);
+
+        assert_eq!(1, result.len());
+        assert_eq!(0..10, result[0]);
+    }
+
+    #[test]
+    fn test_invert_range_left() {
+        let group = create_inverted_test_group();
+
+        let mut values = vec![0..10, 190..210];
+        let mut result = group.apply_range(&mut values);
+        result.sort_by_key(|r| r.start);
+
+        assert_eq!(3, result.len());
+        assert_eq!(0..10, result[0]);
+        assert_eq!(100..110, result[1]); // Mapped value
+        assert_eq!(190..200, result[2]); // Gap portion
+    }
+
+    #[test]
+    fn test_invert_range_right() {
+        let group = create_inverted_test_group();
+
+        let mut values = vec![0..10, 215..235];
+        let mut result = group.apply_range(&mut values);
+        result.sort_by_key(|r| r.start);
+
+        assert_eq!(3, result.len());
+        assert_eq!(0..10, result[0]);
+        assert_eq!(115..125, result[1]); // Mapped value
+        assert_eq!(225..235, result[2]); // Gap portion
+    }
+
+    #[test]
+    fn test_invert_range_value_contained_in_range() {
+        let group = create_inverted_test_group();
+
+        let mut values = vec![Range { start: 15, end: 25 }];
+        let result = group.apply_range(&mut values);
+
+        assert_eq!(1, result.len());
+        assert_eq!(80..90, result[0]);
+    }
+
+    #[test]
+    fn test_invert_range_range_contained_in_value() {
+        let group = create_inverted_test_group();
+
+        let mut values = vec![0..10, 990..1110];
+        let mut result = group.apply_range(&mut values);
+        result.sort_by_key(|r| r.start);
+
+        assert_eq!(4, result.len());
+        assert_eq!(0..10, result[0]);
+        assert_eq!(990..1000, result[1]); // Leading portion
+        assert_eq!(1100..1110, result[2]); // Trailing portion
+        assert_eq!(2000..2100, result[3]); // Mapped value
+    }
+
+    #[test]
+    fn test_seeds_for_location() {
+        let groups = create_example();
+        let seeds = [79..93, 55..68];
+
+        assert_eq!("82", seeds_for_location(&groups, &seeds, "46"));
+        assert_eq!(
+            "62..66\n82..92",
+            seeds_for_location(&groups, &seeds, "40..60")
+        );
+        assert_eq!(
+            "No seeds map to location 3",
+            seeds_for_location(&groups, &seeds, "3")
+        );
+    }
+
+    #[test]
+    fn test_seeds_for_location_not_bijection() {
+        // Seeds 0..10 are moved onto 20..30, which also map to themselves.
+        let groups = create_test_group_from(&["header", "20 0 10"]);
+        let seeds = [Range { start: 0, end: 100 }];
+
+        assert_eq!(
+            "No seeds map to location 5",
+            seeds_for_location(&groups, &seeds, "5")
+        );
+        assert_eq!("5\n25", seeds_for_location(&groups, &seeds, "25"));
+        assert_eq!(
+            "0..5\n15..25",
+            seeds_for_location(&groups, &seeds, "15..25")
+        );
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(
+            vec![0..15, 20..30],
+            merge_ranges(vec![20..30, 10..15, 0..10, 5..8, 40..40])
+        );
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(46..47, parse_range("46"));
+        assert_eq!(40..50, parse_range("40..50"));
+    }
 }
@@ -1,47 +1,65 @@
-use aoc2023::util::{get_all_numbers, get_first_number};
+use aoc2023::{
+    num_theory::count_products_exceeding,
+    util::{get_all_numbers, get_first_number},
+};
 use log::info;
+use num::BigUint;
 
 aoc2023::solver!(part1, part2);
 
 fn part1(lines: &[String]) -> String {
-    let times: Vec<i32> = get_all_numbers(&lines[0]);
-    let distances: Vec<i32> = get_all_numbers(&lines[1]);
-    let mut wins: Vec<_> = Vec::new();
+    // Squaring the times in count_products_exceeding overflows machine
+    // integers once they pass 2^64.
+    let times: Vec<BigUint> = get_all_numbers(&lines[0]);
+    let distances: Vec<BigUint> = get_all_numbers(&lines[1]);
 
     // Distance Traveled
     // (t - x) * x
 
-    for (i, t) in times.into_iter().enumerate() {
-        let target = distances[i];
-        let mut count = 0;
-
-        for x in 0..t {
-            if (t - x) * x > target {
-                count += 1;
-            }
-        }
-
-        wins.push(count)
-    }
-
-    let result: i32 = wins.iter().product();
+    let result: BigUint = times
+        .into_iter()
+        .zip(distances)
+        .map(|(t, target)| count_products_exceeding(t, target))
+        .product();
 
     format!("{}", result)
 }
 
 fn part2(lines: &[String]) -> String {
-    let time: i64 = get_first_number(&lines[0].replace(" ", ""));
+    // Joining the digits can give numbers too large for the square to fit in
+    // a machine integer.
+    let time: BigUint = get_first_number(&lines[0].replace(" ", ""));
     let distance = get_first_number(&lines[1].replace(" ", ""));
 
     info!("Time: {}", time);
     info!("Distance: {}", distance);
 
-    let mut count = 0;
-    for x in 0..time {
-        if (time - x) * x > distance {
-            count += 1;
-        }
-    }
+    let count = count_products_exceeding(time, distance);
 
     format!("{}", count)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let lines = to_lines(&["Time:      7  15   30", "Distance:  9  40  200"]);
+        assert_eq!(part1(&lines), "288");
+        assert_eq!(part2(&lines), "71503");
+    }
+
+    #[test]
+    fn test_time_above_u64() {
+        // With a time of 2^65, every split except holding for none or all of
+        // the time goes further than 0. The second race has 4 ways to win.
+        let lines = to_lines(&["Time: 36893488147419103232 7", "Distance: 0 9"]);
+        let expected: BigUint = ((BigUint::from(1u32) << 65u32) - 1u32) * 4u32;
+        assert_eq!(part1(&lines), expected.to_string());
+    }
+}
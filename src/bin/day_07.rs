@@ -1,123 +1,187 @@
-use std::{collections::HashMap, iter};
-
-use aoc2023::util::get_first_number;
+use aoc2023::{
+    camel_cards::{rank_hands, total_winnings, Hand, Rules, TieBreak},
+    input,
+};
 use log::{info, log_enabled};
 
 aoc2023::solver!(part1, part2);
 
-const NORMAL_RANKS: &str = "23456789TJQKA";
-const WILD_RANKS: &str = "J23456789TQKA";
-
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy)]
-enum HandKind {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
+fn part1(lines: &[String]) -> String {
+    play(lines, Rules::standard())
 }
 
-#[derive(Debug)]
-struct Hand {
-    bid: i32,
-    cards: Vec<char>,
-    kind: HandKind,
+fn part2(lines: &[String]) -> String {
+    play(lines, Rules::jokers_wild())
 }
 
-impl Hand {
-    fn parse(line: &String, jokers_wild: bool) -> Self {
-        let (cards_str, bid_str) = line.split_once(' ').unwrap();
+// Options to play variants of the game:
+//   --ranks <cards>       card ranks from lowest to highest
+//   --hand-size <n>       number of cards in a hand
+//   --wild <cards>        cards that count as anything, e.g. J or JQ
+//   --tie-break <order>   dealt (default) or highest
+//   --output table        print every hand with its kind and winnings
+fn play(lines: &[String], default: Rules) -> String {
+    let rules = Variant::from_options().apply(default);
 
-        let cards: Vec<_> = cards_str.chars().collect();
-        let mut counts = count_cards(cards_str);
-        let bid = get_first_number(bid_str);
+    let hands: Vec<_> = lines.iter().map(|l| Hand::parse(l)).collect();
+    let ranked = rank_hands(&rules, &hands);
 
-        if jokers_wild && counts.len() > 1 {
-            if let Some(jcount) = counts.remove(&'J') {
-                *counts.values_mut().max().unwrap() += jcount;
-            }
+    if log_enabled!(log::Level::Info) {
+        for r in &ranked[..] {
+            info!("{}", r);
         }
-
-        let mut ranks: Vec<_> = counts
-            .into_values()
-            .chain(iter::repeat(0))
-            .take(5)
-            .collect();
-        ranks.sort();
-
-        let kind = match ranks[..] {
-            [0, 0, 0, 0, 5] => HandKind::FiveOfAKind,
-            [0, 0, 0, 1, 4] => HandKind::FourOfAKind,
-            [0, 0, 0, 2, 3] => HandKind::FullHouse,
-            [0, 0, 1, 1, 3] => HandKind::ThreeOfAKind,
-            [0, 0, 1, 2, 2] => HandKind::TwoPair,
-            [0, 1, 1, 1, 2] => HandKind::OnePair,
-            [1, 1, 1, 1, 1] => HandKind::HighCard,
-            _ => panic!("Unmatched hand!"),
-        };
-
-        Hand { bid, cards, kind }
     }
 
-    fn key_by_hand_order(&self, ranks: &str) -> (HandKind, usize, usize, usize, usize, usize) {
-        let values: Vec<_> = self.cards.iter().map(|c| ranks.find(*c).unwrap()).collect();
+    let total = total_winnings(&ranked);
 
-        (
-            self.kind, values[0], values[1], values[2], values[3], values[4],
-        )
+    match input::option::<String>("output").as_deref() {
+        Some("table") => {
+            let rows: Vec<_> = ranked.iter().map(|r| r.to_string()).collect();
+            format!("{}\nTotal winnings {}", rows.join("\n"), total)
+        }
+        Some(o) => panic!("Unknown output: {}", o),
+        None => format!("{}", total),
     }
 }
 
-fn count_cards(cards: &str) -> HashMap<char, i32> {
-    let mut result = HashMap::new();
+// Changes to the part's rules given on the command line. Anything not given
+// is left as the part has it, so part two keeps jokers wild unless --wild
+// replaces them.
+#[derive(Debug, Default)]
+struct Variant {
+    ranks: Option<String>,
+    hand_size: Option<usize>,
+    wild: Option<String>,
+    tie_break: Option<String>,
+}
 
-    for c in cards.chars() {
-        if result.contains_key(&c) {
-            *result.get_mut(&c).unwrap() += 1;
-        } else {
-            result.insert(c, 1);
+impl Variant {
+    fn from_options() -> Self {
+        Self {
+            ranks: input::option("ranks"),
+            hand_size: input::option("hand-size"),
+            wild: input::option("wild"),
+            tie_break: input::option("tie-break"),
         }
     }
 
-    result
-}
+    fn apply(&self, rules: Rules) -> Rules {
+        let rules = match &self.ranks {
+            Some(ranks) => rules.with_ranks(ranks),
+            None => rules,
+        };
 
-fn part1(lines: &[String]) -> String {
-    let mut hands: Vec<_> = lines.iter().map(|l| Hand::parse(l, false)).collect();
-    hands.sort_by_key(|h| h.key_by_hand_order(NORMAL_RANKS));
+        let rules = match self.hand_size {
+            Some(hand_size) => rules.with_hand_size(hand_size),
+            None => rules,
+        };
 
-    if log_enabled!(log::Level::Info) {
-        for h in &hands[..] {
-            info!("{:?}", h);
+        let rules = match &self.wild {
+            Some(wild) => rules.with_wildcards(wild),
+            None => rules,
+        };
+
+        match self.tie_break.as_deref() {
+            Some("dealt") | None => rules,
+            Some("highest") => rules.with_tie_break(TieBreak::HighestFirst),
+            Some(t) => panic!("Unknown tie break: {}", t),
         }
     }
-
-    let score = score_hands(&hands);
-    format!("{}", score)
 }
 
-fn part2(lines: &[String]) -> String {
-    let mut hands: Vec<_> = lines.iter().map(|l| Hand::parse(l, true)).collect();
-    hands.sort_by_key(|h| h.key_by_hand_order(WILD_RANKS));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        [
+            "32T3K 765",
+            "T55J5 684",
+            "KK677 28",
+            "KTJJT 220",
+            "QQQJA 483",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+    }
 
-    if log_enabled!(log::Level::Info) {
-        for h in &hands[..] {
-            info!("{:?}", h);
-        }
+    fn winnings(rules: &Rules, lines: &[&str]) -> u64 {
+        let hands: Vec<_> = lines.iter().map(|l| Hand::parse(l)).collect();
+        total_winnings(&rank_hands(rules, &hands))
     }
 
-    let score = score_hands(&hands);
-    format!("{}", score)
-}
+    #[test]
+    fn test_example() {
+        assert_eq!(part1(&example()), "6440");
+        assert_eq!(part2(&example()), "5905");
+    }
+
+    #[test]
+    fn test_hand_size_keeps_jokers_wild() {
+        let variant = Variant {
+            hand_size: Some(6),
+            ..Default::default()
+        };
+        let rules = variant.apply(Rules::jokers_wild());
 
-fn score_hands(hands: &[Hand]) -> i32 {
-    let mut total: i32 = 0;
+        // Jokers make the first hand six of a kind, where without them it is
+        // only four of a kind.
+        let lines = ["JJAAAA 1", "222223 10"];
+        assert_eq!(winnings(&rules, &lines), 12);
 
-    for (i, h) in hands.iter().enumerate() {
-        total += h.bid * (i as i32 + 1)
+        let standard = variant.apply(Rules::standard());
+        assert_eq!(winnings(&standard, &lines), 21);
     }
 
-    total
+    #[test]
+    fn test_ranks_keep_jokers_wild() {
+        let variant = Variant {
+            ranks: Some("AKQJT98765432".to_string()),
+            ..Default::default()
+        };
+        let rules = variant.apply(Rules::jokers_wild());
+
+        // J makes four of a kind but is lowest, and 2 now ranks above A.
+        assert_eq!(
+            winnings(&rules, &["J2223 1", "22223 10", "AAAA3 100"]),
+            1 + 200 + 30
+        );
+    }
+
+    #[test]
+    fn test_wild_replaces_jokers() {
+        let variant = Variant {
+            wild: Some("Q".to_string()),
+            ..Default::default()
+        };
+        let rules = variant.apply(Rules::jokers_wild());
+
+        // J is natural again, so JJ234 is only a pair and QQ234 is three of a
+        // kind.
+        assert_eq!(winnings(&rules, &["QQ234 1", "JJ234 10"]), 12);
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let variant = Variant {
+            tie_break: Some("highest".to_string()),
+            ..Default::default()
+        };
+        let rules = variant.apply(Rules::standard());
+
+        // Sorted, 2AKQT is AKQT2 and beats A5432.
+        assert_eq!(winnings(&rules, &["2AKQT 1", "A2345 10"]), 12);
+        assert_eq!(winnings(&Rules::standard(), &["2AKQT 1", "A2345 10"]), 21);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_tie_break() {
+        let variant = Variant {
+            tie_break: Some("lowest".to_string()),
+            ..Default::default()
+        };
+        variant.apply(Rules::standard());
+    }
 }
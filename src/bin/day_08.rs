@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 
-use aoc2023::util::extract_all_matches;
+use aoc2023::{
+    graph::dot::Dot,
+    num_theory::{first_common_hit, Cycle},
+    solver::output_path,
+    util::extract_all_matches,
+};
 use log::info;
 
 aoc2023::solver!(part1, part2);
@@ -38,6 +43,8 @@ fn part1(lines: &[String]) -> String {
         map.insert(entry.node.clone(), entry);
     }
 
+    write_dot(&map);
+
     let mut steps = 0;
     let mut pos = "AAA";
     for dir in path {
@@ -60,7 +67,7 @@ fn part1(lines: &[String]) -> String {
 }
 
 fn part2(lines: &[String]) -> String {
-    let path = lines[0].chars().cycle();
+    let path: Vec<_> = lines[0].chars().collect();
     let mut map: HashMap<String, MapEntry> = HashMap::new();
 
     for line in &lines[2..] {
@@ -70,40 +77,74 @@ fn part2(lines: &[String]) -> String {
         map.insert(entry.node.clone(), entry);
     }
 
-    let mut steps = 0;
+    write_dot(&map);
 
-    let mut positions: Vec<_> = map.keys().filter(|k| k.ends_with("A")).collect();
+    let positions: Vec<_> = map.keys().filter(|k| k.ends_with('A')).collect();
     info!("{:?}", positions);
 
-    // Vector to hold solutions for each position.
-    let mut solutions: Vec<Vec<i32>> = positions.iter().map(|_| Vec::new()).collect();
+    // A ghost's state is its node and position in the directions. Once a
+    // state repeats, the ghost is in a cycle and every Z node it reaches
+    // repeats with the same period.
+    let cycles: Vec<_> = positions
+        .iter()
+        .map(|start| {
+            let cycle = Cycle::find(
+                (start.as_str(), 0),
+                |(pos, i)| {
+                    let entry = map.get(*pos).unwrap();
+                    let next = match path[*i] {
+                        'L' => &entry.left,
+                        'R' => &entry.right,
+                        d => panic!("Unexpected direction: {}", d),
+                    };
+                    (next.as_str(), (i + 1) % path.len())
+                },
+                |(pos, _)| pos.ends_with('Z'),
+            );
+
+            info!(
+                "Ghost {} cycle starts at {} with length {}, hits {:?}",
+                start, cycle.start, cycle.length, cycle.hits
+            );
+            cycle
+        })
+        .collect();
+
+    let least_steps = first_common_hit(&cycles, 1).expect("Ghosts never align on Z nodes");
 
-    for dir in path {
-        steps += 1;
-        for (i, pos) in positions.iter_mut().enumerate() {
-            let entry = map.get(*pos).unwrap();
-            *pos = match dir {
-                'L' => &entry.left,
-                'R' => &entry.right,
-                _ => panic!("Unexpected direction: {}", dir),
-            };
-
-            if pos.ends_with('Z') {
-                info!("Position {}, Steps {}", i, steps);
-                solutions[i].push(steps);
-            }
+    format!("{}", least_steps)
+}
+
+// With --dot <file>, write the network with start nodes as boxes and end
+// nodes as double circles.
+fn write_dot(map: &HashMap<String, MapEntry>) {
+    let Some(path) = output_path("dot") else {
+        return;
+    };
+
+    let mut names: Vec<_> = map.keys().collect();
+    names.sort();
+
+    let mut dot = Dot::digraph("day_08");
+    for name in names {
+        let entry = &map[name];
+        if name.ends_with('A') {
+            dot.node(name, &[("shape", "box")]);
+        } else if name.ends_with('Z') {
+            dot.node(name, &[("shape", "doublecircle")]);
+        } else {
+            dot.node(name, &[]);
         }
 
-        if solutions.iter().all(|v| !v.is_empty()) {
-            break;
+        if entry.left == entry.right {
+            dot.edge(name, &entry.left, &[("label", "L,R")]);
+        } else {
+            dot.edge(name, &entry.left, &[("label", "L")]);
+            dot.edge(name, &entry.right, &[("label", "R")]);
         }
     }
 
-    let least_steps: i64 = solutions
-        .iter()
-        .map(|v| v[0] as i64)
-        .reduce(|a, b| num::integer::lcm(a, b))
-        .unwrap();
-
-    format!("{}", least_steps)
+    info!("Writing network to {}", path);
+    dot.write_file(&path)
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", path, e));
 }
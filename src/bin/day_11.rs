@@ -1,36 +1,26 @@
-use std::collections::BTreeSet;
-use std::iter::repeat;
-
-use aoc2023::{collections::grid::Grid, util::combinations};
+use aoc2023::{collections::grid::Grid, input};
+use log::info;
 
 aoc2023::solver!(part1, part2);
 
+// Use --expansion <n> to replace each empty row or column with n of them,
+// instead of the default for the part.
 fn part1(lines: &[String]) -> String {
-    let mut grid: Grid<_> = lines.iter().map(|line| line.chars()).collect();
-
-    expand_grid(&mut grid);
-
-    let galaxies: Vec<_> = grid
-        .enumerate()
-        .filter_map(|(i, j, c)| match *c {
-            '#' => Some((i, j)),
-            _ => None,
-        })
-        .collect();
-
-    let total: usize = combinations(&galaxies)
-        .map(|(a, b)| a.0.abs_diff(b.0) + a.1.abs_diff(b.1))
-        .sum();
-
+    let total = solve(lines, input::option("expansion").unwrap_or(2));
     format!("{}", total)
 }
 
 fn part2(lines: &[String]) -> String {
-    let spacing = 1000000;
-    let grid: Grid<_> = lines.iter().map(|line| line.chars()).collect();
+    let total = solve(lines, input::option("expansion").unwrap_or(1000000));
+    format!("{}", total)
+}
 
-    let empty_cols: BTreeSet<_> = get_empty_cols(&grid).into_iter().collect();
-    let empty_rows: BTreeSet<_> = get_empty_rows(&grid).into_iter().collect();
+fn solve(lines: &[String], expansion: u64) -> u64 {
+    if expansion == 0 {
+        panic!("Expansion must be at least 1");
+    }
+
+    let grid: Grid<_> = lines.iter().map(|line| line.chars()).collect();
 
     let galaxies: Vec<_> = grid
         .enumerate()
@@ -39,59 +29,77 @@ fn part2(lines: &[String]) -> String {
             _ => None,
         })
         .collect();
+    info!("Found {} galaxies", galaxies.len());
 
-    let total: usize = combinations(&galaxies)
-        .map(|(a, b)| {
-            let min_row = a.0.min(b.0);
-            let max_row = a.0.max(b.0);
-
-            let row_count =
-                (max_row - min_row) + (empty_rows.range(min_row..max_row).count() * (spacing - 1));
-
-            let min_col = a.1.min(b.1);
-            let max_col = a.1.max(b.1);
+    // Distances split into independent row and column parts.
+    let rows: Vec<_> = galaxies.iter().map(|g| g.0).collect();
+    let cols: Vec<_> = galaxies.iter().map(|g| g.1).collect();
 
-            let col_count =
-                (max_col - min_col) + (empty_cols.range(min_col..max_col).count() * (spacing - 1));
-            row_count + col_count
-        })
-        .sum();
-
-    format!("{}", total)
-}
-
-fn expand_grid(grid: &mut Grid<char>) {
-    let empty_rows = get_empty_rows(grid);
-
-    empty_rows
-        .iter()
-        .rev()
-        .for_each(|i| grid.insert_row_at(*i, repeat('.')));
-
-    let empty_cols = get_empty_cols(grid);
-
-    empty_cols
-        .iter()
-        .rev()
-        .for_each(|i| grid.insert_col_at(*i, repeat('.')));
+    axis_distance(&rows, grid.rows(), expansion) + axis_distance(&cols, grid.cols(), expansion)
 }
 
-fn get_empty_rows(grid: &Grid<char>) -> Vec<usize> {
-    grid.row_wise_iter()
-        .enumerate()
-        .filter_map(|(i, mut row)| match row.all(|c| c == &'.') {
-            true => Some(i),
-            _ => None,
-        })
-        .collect()
+// Sum of the distances between every pair of positions along one axis, after
+// each line with no positions grows to `expansion` lines.
+fn axis_distance(positions: &[usize], len: usize, expansion: u64) -> u64 {
+    let mut occupied = vec![false; len];
+    positions.iter().for_each(|p| occupied[*p] = true);
+
+    // Position of each line once the empty lines before it have grown.
+    let mut expanded = Vec::with_capacity(len);
+    let mut offset = 0;
+    for o in occupied {
+        expanded.push(offset);
+        offset += if o { 1 } else { expansion };
+    }
+
+    let mut values: Vec<_> = positions.iter().map(|p| expanded[*p]).collect();
+    values.sort_unstable();
+
+    // Once sorted, each value is the far end of a pair with every value
+    // before it, so it adds itself once for each of those minus their sum.
+    let mut total = 0;
+    let mut prefix = 0;
+    for (i, v) in values.iter().enumerate() {
+        total += v * i as u64 - prefix;
+        prefix += v;
+    }
+
+    total
 }
 
-fn get_empty_cols(grid: &Grid<char>) -> Vec<usize> {
-    grid.col_wise_iter()
-        .enumerate()
-        .filter_map(|(i, mut col)| match col.all(|c| c == &'.') {
-            true => Some(i),
-            _ => None,
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        [
+            "...#......",
+            ".......#..",
+            "#.........",
+            "..........",
+            "......#...",
+            ".#........",
+            ".........#",
+            "..........",
+            ".......#..",
+            "#...#.....",
+        ]
+        .iter()
+        .map(|l| l.to_string())
         .collect()
+    }
+
+    #[test]
+    fn test_expansion() {
+        assert_eq!(solve(&example(), 2), 374);
+        assert_eq!(solve(&example(), 10), 1030);
+        assert_eq!(solve(&example(), 100), 8410);
+    }
+
+    #[test]
+    fn test_axis_distance() {
+        // Lines 1 and 2 are empty.
+        assert_eq!(axis_distance(&[0, 3, 3], 4, 1), 6);
+        assert_eq!(axis_distance(&[0, 3, 3], 4, 5), 22);
+    }
 }
@@ -1,57 +1,111 @@
-use std::collections::HashMap;
-
-use aoc2023::util::get_all_numbers;
-use log::{debug, info};
+use aoc2023::{
+    input,
+    memo::{Memo, MemoContext},
+    solver::is_parallel,
+    util::{get_all_numbers, maybe_par_map},
+};
+use log::info;
+use num::{BigUint, One, Zero};
 
 aoc2023::solver!(part1, part2);
 
+// Use --unfold <n> and --joiner <c> to change how part two unfolds each record,
+// and --solver memo to count with the recursive solver instead.
 fn part1(lines: &[String]) -> String {
-    let total: usize = lines.iter().map(|line| count_variations(line)).sum();
+    let total: BigUint = maybe_par_map(lines, is_parallel(), |l| count_variations(l, 1, '?'))
+        .into_iter()
+        .sum();
     format!("{}", total)
 }
 
 fn part2(lines: &[String]) -> String {
-    let total: usize = lines.iter().map(|line| count_folded_variations(line)).sum();
+    let factor = input::option("unfold").unwrap_or(5);
+    let joiner = input::option("joiner").unwrap_or('?');
+    if !['.', '#', '?'].contains(&joiner) {
+        panic!("Invalid joiner: {}", joiner);
+    }
+
+    let total: BigUint = maybe_par_map(lines, is_parallel(), |l| {
+        count_variations(l, factor, joiner)
+    })
+    .into_iter()
+    .sum();
     format!("{}", total)
 }
 
-fn count_variations(line: &String) -> usize {
-    let (spec, values) = line.split_once(' ').unwrap();
-    let runs: Vec<_> = get_all_numbers(values);
-    let springs: Vec<_> = spec.chars().collect();
+fn count_variations(line: &str, factor: usize, joiner: char) -> BigUint {
+    let (springs, runs) = unfold(line, factor, joiner);
 
-    let mut inspector = SpringInspector::new(springs, runs);
-    inspector.count_permutations()
+    match input::option::<String>("solver").as_deref() {
+        None | Some("dp") => count_arrangements(springs.as_bytes(), &runs),
+        Some("memo") => {
+            let inspector = SpringInspector::new(springs.chars().collect(), runs);
+            inspector.count_permutations()
+        }
+        Some(s) => panic!("Unknown solver: {}", s),
+    }
 }
 
-fn count_folded_variations(line: &String) -> usize {
+// Repeat the springs factor times separated by the joiner, and the runs
+// factor times.
+fn unfold(line: &str, factor: usize, joiner: char) -> (String, Vec<usize>) {
     let (spec, values) = line.split_once(' ').unwrap();
 
-    let unfolded_spec = std::iter::repeat(spec)
-        .take(5)
-        .collect::<Vec<&str>>()
-        .join("?");
+    let springs = vec![spec; factor].join(&joiner.to_string());
+    let runs = get_all_numbers(values).repeat(factor);
 
-    let unfolded_values = std::iter::repeat(values)
-        .take(5)
-        .collect::<Vec<&str>>()
-        .join(",");
+    (springs, runs)
+}
+
+// Bottom up count of the ways to fill in the unknown springs. ways[i][j] is
+// the number of arrangements of springs[i..] that make up runs[j..].
+fn count_arrangements(springs: &[u8], runs: &[usize]) -> BigUint {
+    let n = springs.len();
+
+    // operational[i] is the number of '.' in springs[..i], so a run can start
+    // at i when none lie in i..i + run.
+    let operational: Vec<_> = std::iter::once(0)
+        .chain(springs.iter().scan(0, |count, b| {
+            *count += (*b == b'.') as usize;
+            Some(*count)
+        }))
+        .collect();
 
-    let runs: Vec<_> = get_all_numbers(&unfolded_values);
-    let springs: Vec<_> = unfolded_spec.chars().collect();
+    // Row i only reads rows i + 1 up to i + run + 1, so only a window of rows
+    // is kept, with row i stored at i % window.
+    let window = runs.iter().max().unwrap_or(&0) + 2;
+    let mut ways = vec![vec![BigUint::zero(); runs.len() + 1]; window];
+    ways[n % window][runs.len()] = BigUint::one();
+
+    for i in (0..n).rev() {
+        for j in 0..=runs.len() {
+            let mut count = BigUint::zero();
+
+            if springs[i] != b'#' {
+                count += &ways[(i + 1) % window][j];
+            }
 
-    let mut inspector = SpringInspector::new(springs, runs);
-    inspector.count_permutations()
+            if springs[i] != b'.' && j < runs.len() {
+                let end = i + runs[j];
+                if end <= n && operational[end] == operational[i] && springs.get(end) != Some(&b'#')
+                {
+                    count += &ways[(end + 1).min(n) % window][j + 1];
+                }
+            }
+
+            ways[i % window][j] = count;
+        }
+    }
+
+    ways[0][0].clone()
 }
 
 struct SpringInspector {
     springs: Vec<char>,
     runs: Vec<usize>,
-    cache: HashMap<State, usize>,
-    cache_hits: usize,
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
 struct State {
     pos: usize,
     run: usize,
@@ -60,34 +114,24 @@ struct State {
 
 impl SpringInspector {
     fn new(springs: Vec<char>, runs: Vec<usize>) -> Self {
-        Self {
-            springs,
-            runs,
-            cache: HashMap::new(),
-            cache_hits: 0,
-        }
+        Self { springs, runs }
     }
 
-    fn count_permutations(&mut self) -> usize {
+    fn count_permutations(&self) -> BigUint {
         let init = State {
             pos: 0,
             run: 0,
             run_idx: 0,
         };
 
-        let total = self.successors(init);
-        info!("Total was {}. Cache hits {}", total, self.cache_hits);
+        let mut memo = Memo::new();
+        let total = memo.compute(init, |ctx, state| self.successors(ctx, state));
+        info!("Total was {}. Cache hits {}", total, memo.hits());
         return total;
     }
 
-    fn successors(&mut self, state: State) -> usize {
-        if let Some(cached) = self.cache.get(&state) {
-            debug!("Using cached result for {:?} -- {}", state, cached);
-            self.cache_hits += 1;
-            return *cached;
-        }
-
-        let mut result = 0;
+    fn successors(&self, ctx: &mut MemoContext<State, BigUint>, state: &State) -> BigUint {
+        let mut result = BigUint::zero();
 
         if state.pos == self.springs.len() {
             // Reached the end. Only valid if either there's no active run and
@@ -98,9 +142,9 @@ impl SpringInspector {
             if (state.run == 0 && remaining == 0)
                 || (remaining == 1 && state.run == self.runs[state.run_idx])
             {
-                return 1;
+                return BigUint::one();
             } else {
-                return 0;
+                return BigUint::zero();
             }
         }
 
@@ -113,7 +157,7 @@ impl SpringInspector {
                 run_idx: state.run_idx,
             };
 
-            result += self.successors(child)
+            result += ctx.call(child)
         }
 
         if current == '.' || current == '?' {
@@ -146,11 +190,95 @@ impl SpringInspector {
             };
 
             if child.is_some() {
-                result += self.successors(child.unwrap());
+                result += ctx.call(child.unwrap());
             }
         }
 
-        self.cache.insert(state, result);
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<&'static str> {
+        vec![
+            "???.### 1,1,3",
+            ".??..??...?##. 1,1,3",
+            "?#?#?#?#?#?#?#? 1,3,1,6",
+            "????.#...#... 4,1,1",
+            "????.######..#####. 1,6,5",
+            "?###???????? 3,2,1",
+        ]
+    }
+
+    fn memoized(line: &str, factor: usize, joiner: char) -> BigUint {
+        let (springs, runs) = unfold(line, factor, joiner);
+        SpringInspector::new(springs.chars().collect(), runs).count_permutations()
+    }
+
+    #[test]
+    fn test_example() {
+        let total = |factor| -> BigUint {
+            example()
+                .iter()
+                .map(|l| count_variations(l, factor, '?'))
+                .sum()
+        };
+
+        assert_eq!(BigUint::from(21u32), total(1));
+        assert_eq!(BigUint::from(525152u32), total(5));
+    }
+
+    #[test]
+    fn test_unfold() {
+        let (springs, runs) = unfold(".# 1", 3, '.');
+        assert_eq!(".#..#..#", springs);
+        assert_eq!(vec![1, 1, 1], runs);
+    }
+
+    #[test]
+    fn test_matches_memoized() {
+        for line in example() {
+            for factor in 1..=4 {
+                for joiner in ['?', '.', '#'] {
+                    let (springs, runs) = unfold(line, factor, joiner);
+                    assert_eq!(
+                        memoized(line, factor, joiner),
+                        count_arrangements(springs.as_bytes(), &runs),
+                        "{} x{} joined by {}",
+                        line,
+                        factor,
+                        joiner
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_factor() {
+        // Each copy of "#.#" is fixed, so however far it unfolds there is one
+        // arrangement.
+        assert_eq!(count_variations("#.# 1,1", 1000, '.'), BigUint::one());
+
+        let count = count_variations("?#?.??#??? 2,3", 1000, '?');
+        assert!(count > BigUint::from(usize::MAX));
+    }
+
+    #[test]
+    fn test_exceeds_usize() {
+        // Separated by '.' each copy holds exactly one of the runs, in either
+        // of its two cells.
+        let separated = count_variations("?? 1", 70, '.');
+        assert_eq!(BigUint::from(2u32).pow(70), separated);
+        assert!(separated > BigUint::from(usize::MAX));
+
+        // Unknown joiners can only add arrangements.
+        assert!(count_variations("?? 1", 70, '?') > separated);
+
+        // The memoized solver counts past usize too.
+        assert_eq!(separated, memoized("?? 1", 70, '.'));
+    }
+}
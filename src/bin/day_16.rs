@@ -1,20 +1,40 @@
-use std::collections::HashSet;
-
-use aoc2023::collections::grid::Grid;
+use aoc2023::{
+    collections::{bit_grid::BitGrid, grid::Grid},
+    solver::is_parallel,
+    util::maybe_par_map,
+};
 use log::{debug, info};
 
 aoc2023::solver!(part1, part2);
 
-#[derive(Debug)]
-struct Tile {
-    contents: char,
-    visit_dirs: HashSet<Direction>,
+// Tiles a beam has passed through, tracked separately for each direction.
+struct Visited {
+    dirs: [BitGrid; 4],
+}
+
+impl Visited {
+    fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            dirs: std::array::from_fn(|_| BitGrid::new(rows, cols)),
+        }
+    }
+
+    // Returns true if the tile had not been visited in this direction.
+    fn insert(&mut self, pos: Position, dir: Direction) -> bool {
+        self.dirs[dir as usize].insert(pos.0, pos.1)
+    }
+
+    fn energized(&self) -> usize {
+        let mut all = self.dirs[0].clone();
+        self.dirs[1..].iter().for_each(|d| all.union_with(d));
+        all.count_ones()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 struct Position(usize, usize);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     Up,
     Right,
@@ -23,67 +43,59 @@ enum Direction {
 }
 
 fn part1(lines: &[String]) -> String {
-    let mut grid = parse_tiles(lines);
+    let grid = parse_tiles(lines);
+    let mut visited = Visited::new(grid.rows(), grid.cols());
 
-    route_beam(Position(0, 0), Direction::Right, &mut grid);
+    route_beam(Position(0, 0), Direction::Right, &grid, &mut visited);
 
-    let energized = grid
-        .enumerate()
-        .filter(|(_, _, tile)| !tile.visit_dirs.is_empty())
-        .count();
+    let energized = visited.energized();
     format!("{}", energized)
 }
 
 fn part2(lines: &[String]) -> String {
-    let mut grid = parse_tiles(lines);
+    let grid = parse_tiles(lines);
 
     let rows = grid.rows();
     let cols = grid.cols();
 
     // Possible starting points.
-    let points = (0..cols)
+    let points: Vec<_> = (0..cols)
         .map(|c| (Position(0, c), Direction::Down))
         .chain((0..cols).map(|c| (Position(rows - 1, c), Direction::Up)))
         .chain((0..rows).map(|r| (Position(r, 0), Direction::Right)))
-        .chain((0..rows).map(|r| (Position(r, cols - 1), Direction::Left)));
+        .chain((0..rows).map(|r| (Position(r, cols - 1), Direction::Left)))
+        .collect();
 
-    let best = points
-        .map(|start| {
-            // Clear previous values
-            grid.enumerate_mut()
-                .for_each(|(_, _, tile)| tile.visit_dirs.clear());
+    let best = maybe_par_map(&points, is_parallel(), |start| {
+        let mut visited = Visited::new(rows, cols);
 
-            // Route beam
-            route_beam(start.0, start.1, &mut grid);
+        // Route beam
+        route_beam(start.0, start.1, &grid, &mut visited);
 
-            // Calculate score
-            let score = grid
-                .enumerate()
-                .filter(|(_, _, tile)| !tile.visit_dirs.is_empty())
-                .count();
+        // Calculate score
+        let score = visited.energized();
 
-            info!(
-                "Starting at {:?} going {:?} score is {}",
-                start.0, start.1, score
-            );
+        info!(
+            "Starting at {:?} going {:?} score is {}",
+            start.0, start.1, score
+        );
 
-            return score;
-        })
-        .max()
-        .unwrap();
+        score
+    })
+    .into_iter()
+    .max()
+    .unwrap();
 
     format!("{}", best)
 }
 
-fn route_beam(pos: Position, dir: Direction, grid: &mut Grid<Tile>) {
-    if grid.get(pos.0, pos.1).visit_dirs.contains(&dir) {
+fn route_beam(pos: Position, dir: Direction, grid: &Grid<char>, visited: &mut Visited) {
+    if !visited.insert(pos, dir) {
         debug!("Skipping position {:?} in direction {:?}", pos, dir);
         return;
     }
 
-    grid.get_mut(pos.0, pos.1).visit_dirs.insert(dir);
-
-    let contents = grid.get(pos.0, pos.1).contents;
+    let contents = *grid.get(pos.0, pos.1);
 
     let next_dirs = match (dir, contents) {
         // On empty space, continue in current direction
@@ -119,27 +131,21 @@ fn route_beam(pos: Position, dir: Direction, grid: &mut Grid<Tile>) {
 
     for d in next_dirs.iter() {
         match (d, &pos) {
-            (Direction::Up, p) if p.0 > 0 => route_beam(Position(p.0 - 1, p.1), *d, grid),
+            (Direction::Up, p) if p.0 > 0 => route_beam(Position(p.0 - 1, p.1), *d, grid, visited),
             (Direction::Down, p) if p.0 + 1 < grid.rows() => {
-                route_beam(Position(p.0 + 1, p.1), *d, grid)
+                route_beam(Position(p.0 + 1, p.1), *d, grid, visited)
+            }
+            (Direction::Left, p) if p.1 > 0 => {
+                route_beam(Position(p.0, p.1 - 1), *d, grid, visited)
             }
-            (Direction::Left, p) if p.1 > 0 => route_beam(Position(p.0, p.1 - 1), *d, grid),
             (Direction::Right, p) if p.1 + 1 < grid.cols() => {
-                route_beam(Position(p.0, p.1 + 1), *d, grid)
+                route_beam(Position(p.0, p.1 + 1), *d, grid, visited)
             }
             (_, _) => (),
         }
     }
 }
 
-fn parse_tiles(lines: &[String]) -> Grid<Tile> {
-    lines
-        .iter()
-        .map(|line| {
-            line.chars().map(|c| Tile {
-                contents: c,
-                visit_dirs: HashSet::new(),
-            })
-        })
-        .collect()
+fn parse_tiles(lines: &[String]) -> Grid<char> {
+    lines.iter().map(|line| line.chars()).collect()
 }
@@ -1,108 +1,222 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display, ops::Range};
 
+use aoc2023::{
+    graph::{digraph::DiGraph, dot::Dot},
+    input,
+    solver::output_path,
+};
 use log::info;
-use regex::Regex;
+
 aoc2023::solver!(part1, part2);
 
 fn part1(lines: &[String]) -> String {
-    let mut line_iter = lines.into_iter();
-    let rules = parse_rules(&mut line_iter);
+    let mut line_iter = lines.iter();
+    let workflows = parse_workflows(&mut line_iter);
     let parts = parse_parts(&mut line_iter);
+    write_dot(&workflows);
+
+    let program = compile(&workflows).unwrap_or_else(|e| panic!("Invalid workflows: {}", e));
+    if let Some(result) = query(&program, &parts) {
+        return result;
+    }
 
-    let total: i32 = parts
+    let total: u32 = parts
         .iter()
-        .filter(|p| inspect(&rules, p))
-        .map(|p| p.values().sum::<i32>())
+        .filter(|p| program.accepts(p))
+        .map(|p| p.iter().sum::<u32>())
         .sum();
 
     format!("{}", total)
 }
 
 fn part2(lines: &[String]) -> String {
-    let mut line_iter = lines.into_iter();
-    let rules = parse_rules(&mut line_iter);
+    let mut line_iter = lines.iter();
+    let workflows = parse_workflows(&mut line_iter);
+    let parts = parse_parts(&mut line_iter);
+    write_dot(&workflows);
+
+    let program = compile(&workflows).unwrap_or_else(|e| panic!("Invalid workflows: {}", e));
+    if let Some(result) = query(&program, &parts) {
+        return result;
+    }
 
-    let total = count_possibilities(&rules);
+    let total = program.count_accepted();
 
     format!("{}", total)
 }
 
-#[derive(Debug)]
+// Ratings of a part, indexed by Prop.
+type Part = [u32; 4];
+
+// Ratings a set of parts can have, indexed by Prop. Every range is non-empty.
+type PartRange = [Range<u32>; 4];
+
+const RATING_RANGE: Range<u32> = 1..4001;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prop {
+    X,
+    M,
+    A,
+    S,
+}
+
+impl Prop {
+    const ALL: [Prop; 4] = [Prop::X, Prop::M, Prop::A, Prop::S];
+
+    fn parse(name: &str) -> Self {
+        match name {
+            "x" => Prop::X,
+            "m" => Prop::M,
+            "a" => Prop::A,
+            "s" => Prop::S,
+            _ => panic!("Unknown property: {}", name),
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Display for Prop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Prop::X => "x",
+            Prop::M => "m",
+            Prop::A => "a",
+            Prop::S => "s",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
+    Less,
+    Greater,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Condition {
+    prop: Prop,
+    cmp: Cmp,
+    value: u32,
+}
+
+impl Condition {
+    fn parse(spec: &str) -> Self {
+        let Some(pos) = spec.find(['<', '>']) else {
+            panic!("Invalid condition: {}", spec);
+        };
+
+        let (prop, rest) = spec.split_at(pos);
+        let (cmp, value) = rest.split_at(1);
+        let cmp = if cmp == "<" { Cmp::Less } else { Cmp::Greater };
+        let value = value
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid condition value: {}", spec));
+
+        Self {
+            prop: Prop::parse(prop),
+            cmp,
+            value,
+        }
+    }
+
+    fn matches(&self, part: &Part) -> bool {
+        let actual = part[self.prop.index()];
+        match self.cmp {
+            Cmp::Less => actual < self.value,
+            Cmp::Greater => actual > self.value,
+        }
+    }
+
+    // Split the range into the parts that match and the parts that do not.
+    fn split(&self, range: &PartRange) -> (Option<PartRange>, Option<PartRange>) {
+        let r = &range[self.prop.index()];
+        let (matched, unmatched) = match self.cmp {
+            Cmp::Less => (
+                r.start..self.value.clamp(r.start, r.end),
+                self.value.clamp(r.start, r.end)..r.end,
+            ),
+            Cmp::Greater => {
+                let cut = (self.value + 1).clamp(r.start, r.end);
+                (cut..r.end, r.start..cut)
+            }
+        };
+
+        let with = |values: Range<u32>| {
+            if values.is_empty() {
+                return None;
+            }
+            let mut result = range.clone();
+            result[self.prop.index()] = values;
+            Some(result)
+        };
+
+        (with(matched), with(unmatched))
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cmp = match self.cmp {
+            Cmp::Less => '<',
+            Cmp::Greater => '>',
+        };
+        write!(f, "{}{}{}", self.prop, cmp, self.value)
+    }
+}
+
+// A rule without a condition always applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Rule {
-    property: String,
-    op: String,
-    value: i32,
+    condition: Option<Condition>,
     target: String,
 }
 
 impl Rule {
     fn parse(spec: &str) -> Self {
-        let re_rule = Regex::new(r"(\w+)([<>])(\d+):(\w+)").unwrap();
-
-        if let Some(capture) = re_rule.captures(spec) {
-            let property = capture.get(1).unwrap().as_str().to_string();
-            let op = capture.get(2).unwrap().as_str().to_string();
-            let value = capture.get(3).unwrap().as_str().parse::<i32>().unwrap();
-            let target = capture.get(4).unwrap().as_str().to_string();
-
-            Rule {
-                property,
-                op,
-                value,
-                target,
-            }
-        } else {
-            Rule {
-                property: "".to_string(),
-                op: "".to_string(),
-                value: 0,
+        match spec.split_once(':') {
+            Some((condition, target)) => Self {
+                condition: Some(Condition::parse(condition)),
+                target: target.to_string(),
+            },
+            None => Self {
+                condition: None,
                 target: spec.to_string(),
-            }
+            },
         }
     }
+}
 
-    fn apply(&self, part: &Part) -> Option<&str> {
-        if self.op.is_empty() {
-            // Default rule
-            return Some(&self.target);
-        }
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Workflow {
+    name: String,
+    rules: Vec<Rule>,
+}
 
-        let actual = *part.get(&self.property).unwrap();
-        if self.op == ">" && actual > self.value || self.op == "<" && actual < self.value {
-            return Some(&self.target);
-        } else {
-            return None;
+impl Workflow {
+    fn parse(line: &str) -> Self {
+        let Some((name, rules)) = line.strip_suffix('}').and_then(|l| l.split_once('{')) else {
+            panic!("Invalid workflow: {}", line);
+        };
+
+        Self {
+            name: name.to_string(),
+            rules: rules.split(',').map(Rule::parse).collect(),
         }
     }
 }
 
-type RuleMap = HashMap<String, Vec<Rule>>;
-type Part = HashMap<String, i32>;
-type PartRange = HashMap<String, std::ops::Range<i32>>;
-
-fn parse_rules<'a, T>(lines: &mut T) -> RuleMap
+fn parse_workflows<'a, T>(lines: &mut T) -> Vec<Workflow>
 where
     T: Iterator<Item = &'a String>,
 {
-    let re_rule_definition = Regex::new(r"(\w+)\{([^}]*)\}").unwrap();
-
     lines
         .take_while(|line| !line.is_empty())
-        .map(|line| {
-            let capture = re_rule_definition.captures(line).unwrap();
-
-            let name = capture.get(1).unwrap().as_str().to_string();
-
-            let rules: Vec<_> = capture
-                .get(2)
-                .unwrap()
-                .as_str()
-                .split(',')
-                .map(|r| Rule::parse(r))
-                .collect();
-
-            (name, rules)
-        })
+        .map(|line| Workflow::parse(line))
         .collect()
 }
 
@@ -110,96 +224,570 @@ fn parse_parts<'a, T>(lines: &mut T) -> Vec<Part>
 where
     T: Iterator<Item = &'a String>,
 {
-    let re_part = Regex::new(r"(\w+)=(\d+)").unwrap();
-
     lines
+        .filter(|line| !line.is_empty())
         .map(|line| {
-            re_part
-                .captures_iter(line)
-                .map(|c| {
-                    let prop = c.get(1).unwrap().as_str().to_string();
-                    let value = c.get(2).unwrap().as_str().parse::<i32>().unwrap();
-
-                    (prop, value)
-                })
-                .collect()
+            let mut part = [0; 4];
+            line.trim_matches(['{', '}']).split(',').for_each(|rating| {
+                let (prop, value) = rating
+                    .split_once('=')
+                    .unwrap_or_else(|| panic!("Invalid part: {}", line));
+                part[Prop::parse(prop).index()] = value.parse().unwrap();
+            });
+            part
         })
         .collect()
 }
 
-fn inspect(rules: &RuleMap, part: &Part) -> bool {
-    let mut name = "in";
+#[derive(Debug, PartialEq, Eq)]
+enum WorkflowError {
+    // No workflow named "in" to start from.
+    MissingStart,
 
-    loop {
-        let rule = rules.get(name).unwrap();
+    DuplicateWorkflow(String),
 
-        let next = rule.iter().find_map(|r| r.apply(part)).unwrap();
-        match next {
-            "R" => return false,
-            "A" => return true,
-            x => name = x,
-        };
+    UnknownWorkflow { workflow: String, target: String },
+
+    // The last rule of a workflow has a condition, so parts can fall off the
+    // end without being sent anywhere.
+    NoDefault(String),
+
+    // Workflows that can send parts back to each other.
+    Cycle(Vec<String>),
+
+    // Workflows that no part can reach from "in".
+    Unreachable(Vec<String>),
+}
+
+impl Display for WorkflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkflowError::MissingStart => write!(f, "No workflow named in"),
+            WorkflowError::DuplicateWorkflow(name) => {
+                write!(f, "Workflow {} is defined more than once", name)
+            }
+            WorkflowError::UnknownWorkflow { workflow, target } => write!(
+                f,
+                "Workflow {} sends parts to unknown workflow {}",
+                workflow, target
+            ),
+            WorkflowError::NoDefault(name) => {
+                write!(f, "Workflow {} does not end with a default rule", name)
+            }
+            WorkflowError::Cycle(names) => {
+                write!(f, "Workflows {} form a cycle", names.join(", "))
+            }
+            WorkflowError::Unreachable(names) => {
+                write!(
+                    f,
+                    "Workflows {} are never reached from in",
+                    names.join(", ")
+                )
+            }
+        }
     }
 }
 
-fn count_possibilities(rules: &RuleMap) -> usize {
-    let mut all = PartRange::new();
-    all.insert("x".to_string(), 1..4001);
-    all.insert("m".to_string(), 1..4001);
-    all.insert("a".to_string(), 1..4001);
-    all.insert("s".to_string(), 1..4001);
+impl std::error::Error for WorkflowError {}
 
-    let mut to_check: Vec<_> = Vec::new();
-    to_check.push(("in", all.clone()));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Accept,
+    Reject,
+    Workflow(usize),
+}
 
-    let mut total = 0;
+#[derive(Debug)]
+struct CompiledRule {
+    condition: Option<Condition>,
+    target: Target,
+}
 
-    while !to_check.is_empty() {
-        let (name, mut pr) = to_check.pop().unwrap();
+// Workflows with targets resolved to indices. Compiling checks that every
+// part ends up accepted or rejected.
+#[derive(Debug)]
+struct Program {
+    names: Vec<String>,
+    workflows: Vec<Vec<CompiledRule>>,
+    start: usize,
+}
 
-        if name == "R" {
-            continue;
+fn compile(workflows: &[Workflow]) -> Result<Program, WorkflowError> {
+    let mut index = HashMap::new();
+    for (i, w) in workflows.iter().enumerate() {
+        if index.insert(w.name.as_str(), i).is_some() {
+            return Err(WorkflowError::DuplicateWorkflow(w.name.clone()));
         }
+    }
 
-        if name == "A" {
-            info!("Accepted {:?}", pr);
+    let start = *index.get("in").ok_or(WorkflowError::MissingStart)?;
 
-            total += pr
-                .values()
-                .map(|v| v.clone().count() as usize)
-                .product::<usize>();
-            continue;
+    let mut compiled = Vec::with_capacity(workflows.len());
+    let mut graph = DiGraph::new();
+
+    for w in workflows.iter() {
+        if w.rules.last().is_none_or(|r| r.condition.is_some()) {
+            return Err(WorkflowError::NoDefault(w.name.clone()));
+        }
+
+        graph.add_node(w.name.as_str());
+
+        let mut rules = Vec::with_capacity(w.rules.len());
+        for r in w.rules.iter() {
+            let target = match r.target.as_str() {
+                "A" => Target::Accept,
+                "R" => Target::Reject,
+                t => match index.get(t) {
+                    Some(i) => {
+                        graph.add_edge(w.name.as_str(), t);
+                        Target::Workflow(*i)
+                    }
+                    None => {
+                        return Err(WorkflowError::UnknownWorkflow {
+                            workflow: w.name.clone(),
+                            target: t.to_string(),
+                        })
+                    }
+                },
+            };
+
+            rules.push(CompiledRule {
+                condition: r.condition,
+                target,
+            });
         }
+        compiled.push(rules);
+    }
 
-        let rule = rules.get(name).unwrap();
+    for component in graph.strongly_connected_components() {
+        let looped = match component[..] {
+            [n] => graph.successors(n).contains(&n),
+            _ => true,
+        };
+        if looped {
+            let mut names: Vec<_> = component
+                .iter()
+                .map(|n| graph.label(*n).to_string())
+                .collect();
+            names.sort();
+            return Err(WorkflowError::Cycle(names));
+        }
+    }
+
+    let reachable = graph.descendants(graph.index_of(&"in").unwrap());
+    if reachable.len() < graph.len() {
+        let mut unreachable: Vec<_> = (0..graph.len())
+            .filter(|n| !reachable.contains(n))
+            .map(|n| graph.label(n).to_string())
+            .collect();
+        unreachable.sort();
+        return Err(WorkflowError::Unreachable(unreachable));
+    }
 
-        for r in rule {
-            // Default rule, move everything
-            if r.op.is_empty() {
-                to_check.push((&r.target, pr.clone()));
-                continue;
+    Ok(Program {
+        names: workflows.iter().map(|w| w.name.clone()).collect(),
+        workflows: compiled,
+        start,
+    })
+}
+
+// Rule that fired, as indices into Program::workflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Step {
+    workflow: usize,
+    rule: usize,
+}
+
+// Parts with ratings in the given ranges are all accepted by following the
+// same path through the workflows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Region {
+    ranges: PartRange,
+    path: Vec<Step>,
+}
+
+impl Region {
+    fn size(&self) -> u64 {
+        self.ranges.iter().map(|r| r.len() as u64).product()
+    }
+}
+
+impl Program {
+    fn accepts(&self, part: &Part) -> bool {
+        self.trace(part).0
+    }
+
+    // Whether the part is accepted, and the rule that fired in each workflow
+    // it passed through.
+    fn trace(&self, part: &Part) -> (bool, Vec<Step>) {
+        let mut current = self.start;
+        let mut path = Vec::new();
+
+        loop {
+            let (i, rule) = self.workflows[current]
+                .iter()
+                .enumerate()
+                .find(|(_, r)| r.condition.is_none_or(|c| c.matches(part)))
+                .unwrap();
+            path.push(Step {
+                workflow: current,
+                rule: i,
+            });
+
+            match rule.target {
+                Target::Accept => return (true, path),
+                Target::Reject => return (false, path),
+                Target::Workflow(next) => current = next,
+            }
+        }
+    }
+
+    // Number of distinct parts, with every rating in 1..=4000, that are
+    // accepted.
+    fn count_accepted(&self) -> u64 {
+        self.accepted_regions().iter().map(|r| r.size()).sum()
+    }
+
+    // Split the space of all parts into the disjoint regions that end up
+    // accepted, in the order the rules are listed.
+    fn accepted_regions(&self) -> Vec<Region> {
+        let all: PartRange = [0; 4].map(|_| RATING_RANGE);
+        let mut to_check = vec![(Target::Workflow(self.start), all, Vec::new())];
+        let mut result = Vec::new();
+
+        while let Some((target, ranges, path)) = to_check.pop() {
+            let current = match target {
+                Target::Reject => continue,
+                Target::Accept => {
+                    info!("Accepted {:?}", ranges);
+                    result.push(Region { ranges, path });
+                    continue;
+                }
+                Target::Workflow(w) => w,
+            };
+
+            // Push in reverse so the first rule is checked first.
+            let mut next = Vec::new();
+            let mut remaining = Some(ranges);
+            for (i, rule) in self.workflows[current].iter().enumerate() {
+                let Some(ranges) = remaining.take() else {
+                    break;
+                };
+
+                let mut path = path.clone();
+                path.push(Step {
+                    workflow: current,
+                    rule: i,
+                });
+
+                match rule.condition {
+                    None => next.push((rule.target, ranges, path)),
+                    Some(c) => {
+                        let (matched, unmatched) = c.split(&ranges);
+                        if let Some(m) = matched {
+                            next.push((rule.target, m, path));
+                        }
+                        remaining = unmatched;
+                    }
+                }
             }
+            to_check.extend(next.into_iter().rev());
+        }
+
+        result
+    }
+
+    // Readable form of a step, e.g. "px: a<2006 -> qkq".
+    fn describe_step(&self, step: &Step) -> String {
+        let rule = &self.workflows[step.workflow][step.rule];
+        let condition = match rule.condition {
+            Some(c) => c.to_string(),
+            None => "else".to_string(),
+        };
+        let target = match rule.target {
+            Target::Accept => "A",
+            Target::Reject => "R",
+            Target::Workflow(w) => &self.names[w],
+        };
+
+        format!("{}: {} -> {}", self.names[step.workflow], condition, target)
+    }
+
+    fn describe_region(&self, region: &Region) -> String {
+        let ranges: Vec<_> = Prop::ALL
+            .iter()
+            .map(|p| {
+                let r = &region.ranges[p.index()];
+                format!("{}={}..={}", p, r.start, r.end - 1)
+            })
+            .collect();
+        let path: Vec<_> = region.path.iter().map(|s| self.describe_step(s)).collect();
+
+        format!("{} via {}", ranges.join(" "), path.join("; "))
+    }
+
+    fn explain(&self, part: &Part) -> String {
+        let (accepted, path) = self.trace(part);
+        let steps: Vec<_> = path.iter().map(|s| self.describe_step(s)).collect();
+        let verdict = if accepted { "accepted" } else { "rejected" };
+
+        format!("{} {}: {}", format_part(part), verdict, steps.join("; "))
+    }
+}
+
+fn format_part(part: &Part) -> String {
+    let ratings: Vec<_> = Prop::ALL
+        .iter()
+        .map(|p| format!("{}={}", p, part[p.index()]))
+        .collect();
+    format!("{{{}}}", ratings.join(","))
+}
+
+// Answers to --query and --explain, one line per region or part:
+//   --query regions     accepted regions and the path taken by each
+//   --query rejected    why each part in the input was rejected
+//   --explain <part>    path taken by a part, e.g. {x=787,m=2655,a=1222,s=2876}
+fn query(program: &Program, parts: &[Part]) -> Option<String> {
+    if let Some(part) = input::option::<String>("explain") {
+        let part = parse_parts(&mut [part].iter())[0];
+        return Some(program.explain(&part));
+    }
+
+    let lines: Vec<_> = match input::option::<String>("query")?.as_str() {
+        "regions" => program
+            .accepted_regions()
+            .iter()
+            .map(|r| program.describe_region(r))
+            .collect(),
+        "rejected" => parts
+            .iter()
+            .filter(|p| !program.accepts(p))
+            .map(|p| program.explain(p))
+            .collect(),
+        q => panic!("Unknown query: {}", q),
+    };
+
+    Some(lines.join("\n"))
+}
+
+// With --dot <file>, write the workflows with each edge labelled by the
+// condition that sends parts along it.
+fn write_dot(workflows: &[Workflow]) {
+    let Some(path) = output_path("dot") else {
+        return;
+    };
+
+    let mut dot = Dot::digraph("day_19");
+    dot.node("in", &[("shape", "box")]);
+    dot.node("A", &[("shape", "doublecircle"), ("color", "green")]);
+    dot.node("R", &[("shape", "doublecircle"), ("color", "red")]);
+
+    for w in workflows.iter() {
+        dot.node(&w.name, &[]);
+        for r in w.rules.iter() {
+            let label = match r.condition {
+                Some(c) => c.to_string(),
+                None => "else".to_string(),
+            };
+            dot.edge(&w.name, &r.target, &[("label", &label)]);
+        }
+    }
 
-            let prop_range = pr.get(&r.property).unwrap();
-            if !prop_range.contains(&r.value) {
-                continue;
+    info!("Writing workflows to {}", path);
+    dot.write_file(&path)
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", path, e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    fn example() -> Vec<String> {
+        to_lines(&[
+            "px{a<2006:qkq,m>2090:A,rfg}",
+            "pv{a>1716:R,A}",
+            "lnx{m>1548:A,A}",
+            "rfg{s<537:gd,x>2440:R,A}",
+            "qs{s>3448:A,lnx}",
+            "qkq{x<1416:A,crn}",
+            "crn{x>2662:A,R}",
+            "in{s<1351:px,qqz}",
+            "qqz{s>2770:qs,m<1801:hdj,R}",
+            "gd{a>3333:R,R}",
+            "hdj{m>838:A,pv}",
+            "",
+            "{x=787,m=2655,a=1222,s=2876}",
+            "{x=1679,m=44,a=2067,s=496}",
+            "{x=2036,m=264,a=79,s=2244}",
+            "{x=2461,m=1339,a=466,s=291}",
+            "{x=2127,m=1623,a=2188,s=1013}",
+        ])
+    }
+
+    fn compile_lines(lines: &[&str]) -> Result<Program, WorkflowError> {
+        let lines = to_lines(lines);
+        compile(&parse_workflows(&mut lines.iter()))
+    }
+
+    #[test]
+    fn test_parse_workflow() {
+        let workflow = Workflow::parse("px{a<2006:qkq,m>2090:A,rfg}");
+
+        assert_eq!(workflow.name, "px");
+        assert_eq!(
+            workflow.rules[0].condition,
+            Some(Condition {
+                prop: Prop::A,
+                cmp: Cmp::Less,
+                value: 2006
+            })
+        );
+        assert_eq!(workflow.rules[1].target, "A");
+        assert_eq!(workflow.rules[2].condition, None);
+        assert_eq!(workflow.rules[2].target, "rfg");
+    }
+
+    #[test]
+    fn test_condition_split() {
+        let range: PartRange = [1..11, 1..11, 1..11, 1..11];
+
+        let less = Condition::parse("m<5");
+        let (matched, unmatched) = less.split(&range);
+        assert_eq!(matched.unwrap()[1], 1..5);
+        assert_eq!(unmatched.unwrap()[1], 5..11);
+
+        let greater = Condition::parse("m>5");
+        let (matched, unmatched) = greater.split(&range);
+        assert_eq!(matched.unwrap()[1], 6..11);
+        assert_eq!(unmatched.unwrap()[1], 1..6);
+
+        let (matched, unmatched) = Condition::parse("x>20").split(&range);
+        assert_eq!(matched, None);
+        assert_eq!(unmatched, Some(range));
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(part1(&example()), "19114");
+        assert_eq!(part2(&example()), "167409079868000");
+    }
+
+    #[test]
+    fn test_unknown_workflow() {
+        let result = compile_lines(&["in{x<10:foo,A}"]);
+        assert_eq!(
+            result.unwrap_err(),
+            WorkflowError::UnknownWorkflow {
+                workflow: "in".to_string(),
+                target: "foo".to_string()
             }
+        );
+    }
+
+    #[test]
+    fn test_missing_start() {
+        let result = compile_lines(&["foo{x<10:R,A}"]);
+        assert_eq!(result.unwrap_err(), WorkflowError::MissingStart);
+    }
+
+    #[test]
+    fn test_no_default() {
+        let result = compile_lines(&["in{x<10:R}"]);
+        assert_eq!(
+            result.unwrap_err(),
+            WorkflowError::NoDefault("in".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let result = compile_lines(&["in{x<10:a,A}", "a{m>5:b,R}", "b{a<3:A,a}"]);
+        assert_eq!(
+            result.unwrap_err(),
+            WorkflowError::Cycle(vec!["a".to_string(), "b".to_string()])
+        );
+
+        let result = compile_lines(&["in{x<10:in,A}"]);
+        assert_eq!(
+            result.unwrap_err(),
+            WorkflowError::Cycle(vec!["in".to_string()])
+        );
+    }
 
-            if r.op == "<" {
-                let mut new_parts = pr.clone();
-                *new_parts.get_mut(&r.property).unwrap() = prop_range.start..r.value;
-                to_check.push((&r.target, new_parts));
+    #[test]
+    fn test_unreachable() {
+        let result = compile_lines(&["in{x<10:R,A}", "other{A}", "more{A}"]);
+        assert_eq!(
+            result.unwrap_err(),
+            WorkflowError::Unreachable(vec!["more".to_string(), "other".to_string()])
+        );
+    }
 
-                *pr.get_mut(&r.property).unwrap() = r.value..prop_range.end;
-            } else {
-                let mut new_parts = pr.clone();
-                *new_parts.get_mut(&r.property).unwrap() = r.value + 1..prop_range.end;
-                to_check.push((&r.target, new_parts));
+    #[test]
+    fn test_accepted_regions() {
+        let program = compile_lines(&["in{x<10:a,m>5:A,R}", "a{s<3:R,A}"]).unwrap();
+        let regions = program.accepted_regions();
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].ranges, [1..10, 1..4001, 1..4001, 3..4001]);
+        assert_eq!(
+            regions[0].path,
+            vec![
+                Step {
+                    workflow: 0,
+                    rule: 0
+                },
+                Step {
+                    workflow: 1,
+                    rule: 1
+                }
+            ]
+        );
+        assert_eq!(regions[1].ranges, [10..4001, 6..4001, 1..4001, 1..4001]);
+        assert_eq!(
+            program.describe_region(&regions[1]),
+            "x=10..=4000 m=6..=4000 a=1..=4000 s=1..=4000 via in: m>5 -> A"
+        );
+    }
 
-                *pr.get_mut(&r.property).unwrap() = prop_range.start..r.value + 1;
+    #[test]
+    fn test_accepted_regions_disjoint() {
+        let lines = example();
+        let program = compile(&parse_workflows(&mut lines.iter())).unwrap();
+        let regions = program.accepted_regions();
+
+        for (i, a) in regions.iter().enumerate() {
+            for b in regions[i + 1..].iter() {
+                let overlap = (0..4).all(|p| {
+                    a.ranges[p].start < b.ranges[p].end && b.ranges[p].start < a.ranges[p].end
+                });
+                assert!(!overlap, "{:?} overlaps {:?}", a, b);
             }
         }
+
+        // Every region is accepted by evaluating any part inside it.
+        for r in regions.iter() {
+            let corner = r.ranges.clone().map(|p| p.start);
+            assert_eq!(program.trace(&corner), (true, r.path.clone()));
+        }
     }
 
-    total
+    #[test]
+    fn test_explain() {
+        let lines = example();
+        let program = compile(&parse_workflows(&mut lines.iter())).unwrap();
+
+        assert_eq!(
+            program.explain(&[1679, 44, 2067, 496]),
+            "{x=1679,m=44,a=2067,s=496} rejected: in: s<1351 -> px; px: else -> rfg; rfg: s<537 -> gd; gd: else -> R"
+        );
+        assert_eq!(
+            program.explain(&[787, 2655, 1222, 2876]),
+            "{x=787,m=2655,a=1222,s=2876} accepted: in: else -> qqz; qqz: s>2770 -> qs; qs: else -> lnx; lnx: m>1548 -> A"
+        );
+    }
 }
@@ -1,13 +1,34 @@
 use std::{
     collections::{HashMap, VecDeque},
     fmt::Display,
+    fs::File,
+    io::{BufWriter, Write},
 };
 
+use aoc2023::{
+    graph::{digraph::DiGraph, dot::Dot},
+    input,
+    num_theory::{first_common_hit, Cycle},
+    solver::output_path,
+};
 use log::{debug, info};
 
 aoc2023::solver!(part1, part2);
 
+// Options:
+//   --trace <file>          record every pulse sent during part one
+//   --trace-filter <a,b>    only record pulses to or from these modules
+//   --replay <file>         log the module state rebuilt from a recorded trace
+//   --at <press>            press to rebuild the state at, defaults to the last
+//   --dot <file>            write the module graph
+// Traces ending in .jsonl are written and read as JSON Lines, otherwise CSV.
 fn part1(lines: &[String]) -> String {
+    if let Some(path) = input::option::<String>("replay") {
+        for line in replay_file(lines, &path, input::option("at")).lines() {
+            info!("{}", line);
+        }
+    }
+
     let mut modules: HashMap<_, _> = lines
         .iter()
         .map(Module::parse)
@@ -15,8 +36,31 @@ fn part1(lines: &[String]) -> String {
         .collect();
 
     initialize_inputs(&mut modules);
-    let (low, high) = press(&mut modules, 1000);
+    write_dot(&modules);
+
+    let trace_path = output_path("trace");
+    let filter = input::option::<String>("trace-filter");
+    let mut trace = trace_path.as_ref().map(|_| match &filter {
+        Some(f) => Trace::with_filter(f.split(',').collect()),
+        None => Trace::new(),
+    });
+
+    let (low, high) = press(&mut modules, 1000, trace.as_mut());
     info!("Low {}, High {}", low, high);
+
+    if let (Some(path), Some(trace)) = (trace_path, trace) {
+        info!("Writing {} pulses to {}", trace.events().len(), path);
+        let mut writer = BufWriter::new(
+            File::create(&path).unwrap_or_else(|_| panic!("Unable to create file: {}", path)),
+        );
+        let result = if is_json_lines(&path) {
+            trace.write_json_lines(&mut writer)
+        } else {
+            trace.write_csv(&mut writer)
+        };
+        result.unwrap_or_else(|e| panic!("Failed to write trace {}: {}", path, e));
+    }
+
     let total = low * high;
     format!("{}", total)
 }
@@ -29,15 +73,311 @@ fn part2(lines: &[String]) -> String {
         .collect();
 
     initialize_inputs(&mut modules);
-
-    // Not fully solved in code. Target RX is the output of a conjunction module
-    // with multiple cyclical inputs. Used this search implementation to find
-    // the cycle lengths of each of those individually and manually multiplied
-    // the result.
-    let total = press_until_rx(&mut modules);
+    write_dot(&modules);
+
+    // Target RX is the output of a conjunction module with multiple cyclical
+    // inputs. When each input's counter resets at the same press it repeats,
+    // this is the LCM of the cycle lengths.
+    let graph = build_graph(&modules);
+    let (feeder, inputs) = find_rx_inputs(&graph);
+    let total = press_until_rx(&mut modules, feeder, &inputs);
     format!("{}", total)
 }
 
+fn build_graph<'a>(modules: &HashMap<&'a str, Module<'a>>) -> DiGraph<&'a str> {
+    let mut graph = DiGraph::new();
+    graph.add_edge("button", "broadcaster");
+
+    for module in sorted_modules(modules) {
+        for d in module.destinations.iter() {
+            graph.add_edge(module.name, *d);
+        }
+    }
+
+    graph
+}
+
+// Modules in name order, so anything built from them comes out the same on
+// every run.
+fn sorted_modules<'a, 'b>(modules: &'b HashMap<&'a str, Module<'a>>) -> Vec<&'b Module<'a>> {
+    let mut sorted: Vec<_> = modules.values().collect();
+    sorted.sort_by_key(|m| m.name);
+    sorted
+}
+
+// With --dot <file>, write the module graph. Flip flops are boxes and
+// conjunctions are diamonds.
+fn write_dot(modules: &HashMap<&str, Module>) {
+    let Some(path) = output_path("dot") else {
+        return;
+    };
+
+    let mut dot = Dot::from_digraph("day_20", &build_graph(modules));
+    for module in sorted_modules(modules) {
+        match module.op {
+            Some("%") => dot.node(module.name, &[("shape", "box")]),
+            Some("&") => dot.node(module.name, &[("shape", "diamond")]),
+            _ => dot.node(module.name, &[("shape", "doubleoctagon")]),
+        }
+    }
+
+    info!("Writing module graph to {}", path);
+    dot.write_file(&path)
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", path, e));
+}
+
+// Find the module sending pulses to rx, and the modules sending pulses to it.
+// Each of those inputs is driven by a separate counter made up of flip flops
+// and a conjunction.
+fn find_rx_inputs<'a>(graph: &DiGraph<&'a str>) -> (&'a str, Vec<&'a str>) {
+    let rx = graph.index_of(&"rx").expect("No module sends pulses to rx");
+
+    let feeder = match graph.predecessors(rx) {
+        [f] => *f,
+        p => panic!("Expected a single module feeding rx, found {}", p.len()),
+    };
+
+    let inputs: Vec<_> = graph
+        .predecessors(feeder)
+        .iter()
+        .map(|i| *graph.label(*i))
+        .collect();
+    info!(
+        "Module {} feeds rx, with inputs {:?}",
+        graph.label(feeder),
+        inputs
+    );
+
+    // Log the loops that make up each counter. The counter is everything
+    // upstream of the input, apart from the button and broadcaster shared by
+    // all of them.
+    for input in inputs.iter() {
+        let nodes: Vec<_> = graph
+            .ancestors(graph.index_of(input).unwrap())
+            .into_iter()
+            .filter(|n| !["button", "broadcaster"].contains(graph.label(*n)))
+            .collect();
+        let counter = graph.subgraph(&nodes);
+
+        for component in counter.strongly_connected_components() {
+            if component.len() > 1 {
+                info!(
+                    "Input {} is driven by a loop of {} modules",
+                    input,
+                    component.len()
+                );
+            }
+        }
+    }
+
+    (graph.label(feeder), inputs)
+}
+
+fn replay_file(lines: &[String], path: &str, at: Option<usize>) -> String {
+    let trace_lines = input::read_lines(path);
+    let trace = if is_json_lines(path) {
+        Trace::parse_json_lines(&trace_lines)
+    } else {
+        Trace::parse_csv(&trace_lines)
+    };
+
+    let mut modules: HashMap<_, _> = lines
+        .iter()
+        .map(Module::parse)
+        .map(|m| (m.name, m))
+        .collect();
+    initialize_inputs(&mut modules);
+
+    let at = at.unwrap_or_else(|| trace.events().last().map_or(0, |e| e.press));
+    info!("Replaying {} up to press {}", path, at);
+    trace.replay(&mut modules, at);
+
+    describe_state(&modules)
+}
+
+fn is_json_lines(path: &str) -> bool {
+    path.ends_with(".jsonl")
+}
+
+// One line per stateful module, in name order.
+fn describe_state(modules: &HashMap<&str, Module>) -> String {
+    let mut names: Vec<_> = modules.keys().copied().collect();
+    names.sort();
+
+    names
+        .iter()
+        .filter_map(|n| {
+            let module = &modules[n];
+            match module.op {
+                Some("%") => Some(format!("%{} {}", n, module.state)),
+                Some("&") => {
+                    let mut inputs: Vec<_> = module.inputs.iter().collect();
+                    inputs.sort_by_key(|(i, _)| **i);
+                    let inputs: Vec<_> =
+                        inputs.iter().map(|(i, k)| format!("{}={}", i, k)).collect();
+                    Some(format!("&{} {}", n, inputs.join(",")))
+                }
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TraceEvent<'a> {
+    press: usize,
+    // Pulses still waiting in the queue when this one was delivered.
+    depth: usize,
+    pulse: Pulse<'a>,
+}
+
+// Record of pulses sent while pressing the button. With a filter, only pulses
+// to or from the named modules are kept. That is still enough to replay the
+// state of those modules, as a module only changes on pulses sent to it.
+#[derive(Debug, Default)]
+struct Trace<'a> {
+    events: Vec<TraceEvent<'a>>,
+    filter: Option<Vec<&'a str>>,
+}
+
+impl<'a> Trace<'a> {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_filter(modules: Vec<&'a str>) -> Self {
+        Self {
+            events: Vec::new(),
+            filter: Some(modules),
+        }
+    }
+
+    fn events(&self) -> &[TraceEvent<'a>] {
+        &self.events
+    }
+
+    fn record(&mut self, press: usize, depth: usize, pulse: &Pulse<'a>) {
+        let keep = match &self.filter {
+            Some(f) => f.contains(&pulse.source) || f.contains(&pulse.destination),
+            None => true,
+        };
+
+        if keep {
+            self.events.push(TraceEvent {
+                press,
+                depth,
+                pulse: *pulse,
+            });
+        }
+    }
+
+    fn write_csv<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writeln!(writer, "press,depth,source,destination,kind")?;
+        for e in self.events.iter() {
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                e.press, e.depth, e.pulse.source, e.pulse.destination, e.pulse.kind
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_json_lines<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for e in self.events.iter() {
+            writeln!(
+                writer,
+                r#"{{"press":{},"depth":{},"source":"{}","destination":"{}","kind":"{}"}}"#,
+                e.press, e.depth, e.pulse.source, e.pulse.destination, e.pulse.kind
+            )?;
+        }
+        Ok(())
+    }
+
+    fn parse_csv(lines: &'a [String]) -> Self {
+        let events = lines
+            .iter()
+            .skip(1)
+            .filter(|l| !l.is_empty())
+            .map(|l| match l.split(',').collect::<Vec<_>>()[..] {
+                [press, depth, source, destination, kind] => {
+                    TraceEvent::new(press, depth, source, destination, kind)
+                }
+                _ => panic!("Invalid trace line: {}", l),
+            })
+            .collect();
+
+        Self {
+            events,
+            filter: None,
+        }
+    }
+
+    // Only reads the flat objects written by write_json_lines.
+    fn parse_json_lines(lines: &'a [String]) -> Self {
+        let events = lines
+            .iter()
+            .filter(|l| !l.is_empty())
+            .map(|l| {
+                let field = |name: &str| {
+                    let key = format!("\"{}\":", name);
+                    let start = l
+                        .find(&key)
+                        .unwrap_or_else(|| panic!("Missing {} in trace line: {}", name, l))
+                        + key.len();
+                    let end = l[start..].find([',', '}']).map_or(l.len(), |e| start + e);
+                    l[start..end].trim_matches('"')
+                };
+
+                TraceEvent::new(
+                    field("press"),
+                    field("depth"),
+                    field("source"),
+                    field("destination"),
+                    field("kind"),
+                )
+            })
+            .collect();
+
+        Self {
+            events,
+            filter: None,
+        }
+    }
+
+    // Deliver every recorded pulse up to and including the given press. The
+    // modules should be freshly initialized, and only modules that had their
+    // incoming pulses recorded end up in the right state.
+    fn replay(&self, modules: &mut HashMap<&'a str, Module<'a>>, press: usize) {
+        for e in self.events.iter().take_while(|e| e.press <= press) {
+            if let Some(m) = modules.get_mut(e.pulse.destination) {
+                m.apply(&e.pulse);
+            }
+        }
+    }
+}
+
+impl<'a> TraceEvent<'a> {
+    fn new(press: &str, depth: &str, source: &'a str, destination: &'a str, kind: &str) -> Self {
+        let kind = match kind {
+            "low" => PulseKind::Low,
+            "high" => PulseKind::High,
+            _ => panic!("Invalid pulse kind: {}", kind),
+        };
+
+        Self {
+            press: press.parse().unwrap(),
+            depth: depth.parse().unwrap(),
+            pulse: Pulse {
+                source,
+                destination,
+                kind,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PulseKind {
     Low,
@@ -53,6 +393,7 @@ impl Display for PulseKind {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Pulse<'a> {
     source: &'a str,
     destination: &'a str,
@@ -172,82 +513,224 @@ fn initialize_inputs<'a>(modules: &mut HashMap<&'a str, Module<'a>>) {
     }
 }
 
-fn press(modules: &mut HashMap<&str, Module>, count: usize) -> (usize, usize) {
+// Push the button once, passing every pulse sent to the observer before it is
+// delivered, along with the number of pulses still waiting behind it.
+fn press_once<'a, F>(modules: &mut HashMap<&'a str, Module<'a>>, observer: &mut F)
+where
+    F: FnMut(&Pulse<'a>, usize),
+{
     let mut signals: VecDeque<_> = VecDeque::new();
+    signals.push_back(Pulse {
+        source: "button",
+        destination: "broadcaster",
+        kind: PulseKind::Low,
+    });
+
+    while let Some(current) = signals.pop_front() {
+        debug!("{}", current);
+        observer(&current, signals.len());
+
+        if let Some(result) = modules
+            .get_mut(current.destination)
+            .and_then(|m| m.apply(&current))
+        {
+            signals.extend(result);
+        }
+    }
+}
+
+fn press<'a>(
+    modules: &mut HashMap<&'a str, Module<'a>>,
+    count: usize,
+    mut trace: Option<&mut Trace<'a>>,
+) -> (usize, usize) {
     info!("Pressing the button {} times.", count);
 
     let mut low: usize = 0;
     let mut high: usize = 0;
-    for _ in 0..count {
-        signals.push_back(Pulse {
-            source: "button",
-            destination: "broadcaster",
-            kind: PulseKind::Low,
-        });
-
-        while !signals.is_empty() {
-            let current = signals.pop_front().unwrap();
-            debug!("{}", current);
+    for n in 1..=count {
+        press_once(modules, &mut |pulse, depth| {
+            if let Some(t) = trace.as_mut() {
+                t.record(n, depth, pulse);
+            }
 
-            match current.kind {
+            match pulse.kind {
                 PulseKind::Low => low += 1,
                 PulseKind::High => high += 1,
-            };
-
-            if let Some(result) = modules
-                .get_mut(current.destination)
-                .and_then(|m| m.apply(&current))
-            {
-                signals.extend(result);
             }
-        }
+        });
     }
 
     (low, high)
 }
 
-fn press_until_rx(modules: &mut HashMap<&str, Module>) -> usize {
-    let mut signals: VecDeque<_> = VecDeque::new();
+// The feeder only sends a low pulse to rx once every input has sent it a high
+// pulse during the same press. Each input is driven by an independent counter,
+// so record the presses where each input goes high until the period of every
+// counter is known, then find the first press where they all line up.
+fn press_until_rx<'a>(
+    modules: &mut HashMap<&'a str, Module<'a>>,
+    feeder: &str,
+    inputs: &[&str],
+) -> usize {
+    let mut hits: HashMap<&str, Vec<usize>> = inputs.iter().map(|i| (*i, Vec::new())).collect();
 
     let mut presses: usize = 0;
-    let mut count = 0;
-    loop {
+    let mut rx_low = false;
+    while !rx_low && hits.values().any(|h| h.len() < 3) {
         presses += 1;
-        signals.push_back(Pulse {
-            source: "button",
-            destination: "broadcaster",
-            kind: PulseKind::Low,
-        });
 
         if presses % 1000000 == 0 {
             info!("Pressed {}", presses);
         }
 
-        while !signals.is_empty() {
-            let current = signals.pop_front().unwrap();
-            debug!("{}", current);
-
-            if current.destination == "rx" && current.kind == PulseKind::Low {
-                return presses;
+        press_once(modules, &mut |pulse, _| {
+            if pulse.destination == "rx" && pulse.kind == PulseKind::Low {
+                rx_low = true;
             }
 
-            if current.source == "zk"
-                && current.destination == "hj"
-                && current.kind == PulseKind::High
-            {
-                info!("Input {} high during press {}", current.source, presses);
-                count += 1;
-                if count >= 10 {
-                    return presses;
+            if pulse.destination == feeder && pulse.kind == PulseKind::High {
+                if let Some(h) = hits.get_mut(pulse.source) {
+                    if h.last() != Some(&presses) {
+                        info!("Input {} high during press {}", pulse.source, presses);
+                        h.push(presses);
+                    }
                 }
             }
+        });
+    }
+
+    if rx_low {
+        return presses;
+    }
 
-            if let Some(result) = modules
-                .get_mut(current.destination)
-                .and_then(|m| m.apply(&current))
-            {
-                signals.extend(result);
+    let cycles: Vec<_> = inputs
+        .iter()
+        .map(|i| {
+            let h = &hits[i];
+            let length = h[1] - h[0];
+            if h[2] - h[1] != length {
+                panic!("Input {} is not periodic, went high at {:?}", i, h);
+            }
+
+            info!(
+                "Input {} first high at {}, cycle length {}",
+                i, h[0], length
+            );
+            Cycle {
+                start: h[0],
+                length,
+                hits: vec![h[0]],
             }
+        })
+        .collect();
+
+    first_common_hit(&cycles, 1).expect("Inputs never all go high in the same press")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        [
+            "broadcaster -> a",
+            "%a -> inv, con",
+            "&inv -> b",
+            "%b -> con",
+            "&con -> output",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+    }
+
+    fn create_modules(lines: &[String]) -> HashMap<&str, Module<'_>> {
+        let mut modules: HashMap<_, _> = lines
+            .iter()
+            .map(Module::parse)
+            .map(|m| (m.name, m))
+            .collect();
+        initialize_inputs(&mut modules);
+        modules
+    }
+
+    #[test]
+    fn test_trace_depth() {
+        let lines = example();
+        let mut modules = create_modules(&lines);
+        let mut trace = Trace::new();
+        press(&mut modules, 1, Some(&mut trace));
+
+        let events = trace.events();
+        assert_eq!(events.len(), 8);
+        assert_eq!(events[0].pulse.destination, "broadcaster");
+        assert_eq!(events[0].depth, 0);
+        // a sends to inv and con, so con is still queued when inv receives.
+        assert_eq!(events[2].pulse.destination, "inv");
+        assert_eq!(events[2].depth, 1);
+    }
+
+    #[test]
+    fn test_trace_filter() {
+        let lines = example();
+        let mut modules = create_modules(&lines);
+        let mut trace = Trace::with_filter(vec!["b"]);
+        press(&mut modules, 2, Some(&mut trace));
+
+        assert!(trace
+            .events()
+            .iter()
+            .all(|e| e.pulse.source == "b" || e.pulse.destination == "b"));
+        assert_eq!(trace.events().len(), 3);
+    }
+
+    #[test]
+    fn test_replay() {
+        let lines = example();
+
+        for at in 0..4 {
+            let mut modules = create_modules(&lines);
+            let mut trace = Trace::new();
+            press(&mut modules, 4, Some(&mut trace));
+
+            let mut expected = create_modules(&lines);
+            press(&mut expected, at, None);
+
+            let mut replayed = create_modules(&lines);
+            trace.replay(&mut replayed, at);
+            assert_eq!(describe_state(&replayed), describe_state(&expected));
         }
     }
+
+    #[test]
+    fn test_round_trip() {
+        let lines = example();
+        let mut modules = create_modules(&lines);
+        let mut trace = Trace::new();
+        press(&mut modules, 3, Some(&mut trace));
+
+        let mut csv = Vec::new();
+        trace.write_csv(&mut csv).unwrap();
+        let csv: Vec<_> = String::from_utf8(csv)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(csv[0], "press,depth,source,destination,kind");
+        assert_eq!(Trace::parse_csv(&csv).events(), trace.events());
+
+        let mut json = Vec::new();
+        trace.write_json_lines(&mut json).unwrap();
+        let json: Vec<_> = String::from_utf8(json)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(
+            json[0],
+            r#"{"press":1,"depth":0,"source":"button","destination":"broadcaster","kind":"low"}"#
+        );
+        assert_eq!(Trace::parse_json_lines(&json).events(), trace.events());
+    }
 }
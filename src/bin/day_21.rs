@@ -1,6 +1,7 @@
-use std::collections::{HashSet, VecDeque};
-
-use aoc2023::collections::grid::Grid;
+use aoc2023::{
+    collections::{bit_grid::BitGrid, grid::Grid},
+    sequence::DifferenceTable,
+};
 use log::{debug, info};
 
 aoc2023::solver!(part1, part2);
@@ -10,72 +11,6 @@ struct Point(usize, usize);
 
 type Garden = Grid<char>;
 
-struct Path<'a> {
-    seen: HashSet<Point>,
-    goal_depth: usize,
-    garden: &'a Garden,
-    rows: usize,
-    cols: usize,
-}
-
-impl<'a> Path<'a> {
-    fn new(garden: &'a Garden, goal_depth: usize) -> Self {
-        let seen = HashSet::new();
-
-        let rows = garden.rows();
-        let cols = garden.cols();
-
-        Path {
-            seen,
-            goal_depth,
-            garden,
-            rows,
-            cols,
-        }
-    }
-
-    fn visit(&mut self, start: Point, start_depth: usize) -> usize {
-        let mut to_visit: VecDeque<_> = VecDeque::new();
-        to_visit.push_back((start, start_depth));
-
-        let mut count = 0;
-
-        while !to_visit.is_empty() {
-            let (current, depth) = to_visit.pop_front().unwrap();
-
-            if !self.is_passable(&current) || self.seen.contains(&current) {
-                continue;
-            }
-
-            self.seen.insert(current);
-
-            if depth % 2 == self.goal_depth % 2 {
-                count += 1;
-            }
-
-            if depth == self.goal_depth {
-                continue;
-            }
-
-            let d = depth + 1;
-            to_visit.push_back((Point(current.0 - 1, current.1), d));
-            to_visit.push_back((Point(current.0 + 1, current.1), d));
-            to_visit.push_back((Point(current.0, current.1 - 1), d));
-            to_visit.push_back((Point(current.0, current.1 + 1), d));
-        }
-
-        debug!("Estimate says {}", count);
-        count
-    }
-
-    fn is_passable(&self, current: &Point) -> bool {
-        return *self
-            .garden
-            .get(current.0 % self.rows, current.1 % self.cols)
-            != '#';
-    }
-}
-
 fn part1(lines: &[String]) -> String {
     let garden: Garden = lines.iter().map(|line| line.chars()).collect();
     let reachable = walk(&garden, 64);
@@ -104,21 +39,15 @@ fn part2(lines: &[String]) -> String {
         results.push(paths);
     }
 
-    let diff = (results[2] - results[1]) - (results[1] - results[0]);
-    info!("Diff is {}", diff);
+    let values: Vec<_> = results.iter().map(|v| *v as i64).collect();
+    let table = DifferenceTable::new(&values);
+    info!("Differences {:?}", table.rows());
 
-    // Check
-    let diff2 = (results[3] - results[2]) - (results[2] - results[1]);
-    if diff != diff2 {
+    if table.degree() != Some(2) {
         panic!("Quadratic assumption did not hold");
     }
 
-    let mut total = results[1];
-    let mut last_diff = results[1] - results[0];
-    for _ in 2..=202300 {
-        last_diff += diff;
-        total += last_diff;
-    }
+    let total = table.forward(202300);
 
     format!("{}", total)
 }
@@ -136,12 +65,31 @@ fn find_start(garden: &Garden) -> Point {
 fn walk(garden: &Garden, steps: usize) -> usize {
     let start = find_start(garden);
 
-    // Offset start to avoid overflow.
-    let offset = Point(
-        start.0 + (garden.rows() * steps),
-        start.1 + (garden.cols() * steps),
-    );
+    // Only cells within `steps` of the start can be reached, so lay out enough
+    // copies of the garden to cover that area with the start in the middle.
+    let size = 2 * steps + 1;
+    let mut open = BitGrid::new(size, size);
+    for i in 0..size {
+        let r = (start.0 + i + garden.rows() * steps - steps) % garden.rows();
+        for j in 0..size {
+            let c = (start.1 + j + garden.cols() * steps - steps) % garden.cols();
+            if *garden.get(r, c) != '#' {
+                open.insert(i, j);
+            }
+        }
+    }
+
+    // Every cell reachable in exactly n steps is a neighbor of a cell
+    // reachable in n - 1 steps.
+    let mut reachable = BitGrid::new(size, size);
+    reachable.insert(steps, steps);
+
+    for _ in 0..steps {
+        reachable = reachable.neighbors();
+        reachable.intersect_with(&open);
+    }
 
-    let mut path = Path::new(garden, steps);
-    path.visit(offset, 0)
+    let count = reachable.count_ones();
+    debug!("Reachable in {} steps: {}", steps, count);
+    count
 }
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use aoc2023::util::get_all_numbers;
+use aoc2023::{graph::dag::Dag, util::get_all_numbers};
 use log::{debug, info};
 
 aoc2023::solver!(part1, part2);
@@ -54,7 +54,8 @@ fn part1(lines: &[String]) -> String {
     let mut bricks: Vec<_> = lines.iter().map(Brick::parse).collect();
     settle(&mut bricks);
 
-    let removable = find_removable(&bricks);
+    let supports = build_supports(&bricks);
+    let removable = find_removable(&supports, bricks.len());
     format!("{}", removable)
 }
 
@@ -62,7 +63,8 @@ fn part2(lines: &[String]) -> String {
     let mut bricks: Vec<_> = lines.iter().map(Brick::parse).collect();
     settle(&mut bricks);
 
-    let will_fall = find_chain(&bricks);
+    let supports = build_supports(&bricks);
+    let will_fall = find_chain(&supports, bricks.len());
     format!("{}", will_fall)
 }
 
@@ -105,38 +107,59 @@ fn settle(bricks: &mut [Brick]) -> usize {
     total
 }
 
-fn find_removable(bricks: &[Brick]) -> usize {
-    let mut removable: HashSet<_> = bricks.iter().enumerate().map(|(i, _)| i).collect();
+// Graph with an edge from each brick to every brick resting on it. The
+// ground is an extra node after the bricks, supporting every brick at the
+// bottom.
+fn build_supports(bricks: &[Brick]) -> Dag {
+    let ground = bricks.len();
+    let mut supports = Dag::new(bricks.len() + 1);
 
-    for i in 0..bricks.len() {
-        let supports: Vec<_> = bricks
+    for (i, brick) in bricks.iter().enumerate() {
+        if brick.start.2 == 1 {
+            supports.add_edge(ground, i);
+        }
+
+        bricks
             .iter()
             .enumerate()
-            .filter(|(j, b)| i != *j && bricks[i].is_on(b))
-            .collect();
-
-        if supports.len() == 1 {
-            info!(
-                "Brick {} is not removable because it supports brick {}",
-                supports[0].0, i
-            );
-            removable.remove(&supports[0].0);
-        }
+            .filter(|(j, b)| i != *j && brick.is_on(b))
+            .for_each(|(j, _)| supports.add_edge(j, i));
     }
 
-    return removable.len();
+    supports
 }
 
-fn find_chain(bricks: &Vec<Brick>) -> usize {
-    let mut total = 0;
+fn find_removable(supports: &Dag, count: usize) -> usize {
+    (0..count)
+        .filter(|i| {
+            let only_support = supports
+                .successors(*i)
+                .iter()
+                .find(|above| supports.predecessors(**above).len() == 1);
 
-    for i in 0..bricks.len() {
-        info!("Removing brick {}", i);
-        let mut copied = bricks.to_owned();
-        copied.remove(i);
+            if let Some(above) = only_support {
+                info!(
+                    "Brick {} is not removable because it supports brick {}",
+                    i, above
+                );
+            }
 
-        total += settle(&mut copied);
-    }
+            only_support.is_none()
+        })
+        .count()
+}
 
-    total
+// A brick falls when a removed brick is on every path from the ground up to
+// it, so the number of falling bricks is the number of bricks dominated by the
+// removed brick.
+fn find_chain(supports: &Dag, count: usize) -> usize {
+    let tree = supports.dominators(count);
+
+    (0..count)
+        .map(|i| {
+            let falling = tree.dominated_count(i);
+            debug!("Removing brick {} drops {} bricks", i, falling);
+            falling
+        })
+        .sum()
 }
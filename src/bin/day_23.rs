@@ -1,6 +1,4 @@
-use std::collections::HashSet;
-
-use aoc2023::collections::grid::Grid;
+use aoc2023::collections::{bit_grid::BitGrid, grid::Grid};
 use log::{debug, info};
 
 aoc2023::solver!(part1, part2);
@@ -92,7 +90,7 @@ fn part2(lines: &[String]) -> String {
 }
 
 fn search(maze: &Maze, start: &Point, end: &Point) -> usize {
-    let mut seen: HashSet<Point> = HashSet::new();
+    let mut seen = BitGrid::new(maze.grid.rows(), maze.grid.cols());
 
     visit(maze, start, end, &mut seen)
 }
@@ -103,7 +101,7 @@ enum CommandType {
     Exit,
 }
 
-fn visit(maze: &Maze, start: &Point, end: &Point, seen: &mut HashSet<Point>) -> usize {
+fn visit(maze: &Maze, start: &Point, end: &Point, seen: &mut BitGrid) -> usize {
     let mut to_visit: Vec<_> = Vec::new();
     to_visit.push((CommandType::Visit, *start, 0));
 
@@ -121,11 +119,13 @@ fn visit(maze: &Maze, start: &Point, end: &Point, seen: &mut HashSet<Point>) ->
 
         match cmd {
             CommandType::Enter => {
-                seen.insert(current);
+                seen.insert(current.0, current.1);
             }
             CommandType::Visit => {
                 debug!("Visiting {:?}", current);
-                if seen.contains(&current) || *maze.grid.get(current.0, current.1) == '#' {
+                if seen.contains(current.0, current.1)
+                    || *maze.grid.get(current.0, current.1) == '#'
+                {
                     debug!("Dead end");
                     continue;
                 }
@@ -153,7 +153,7 @@ fn visit(maze: &Maze, start: &Point, end: &Point, seen: &mut HashSet<Point>) ->
                 to_visit.push((CommandType::Enter, current, steps + 1));
             }
             CommandType::Exit => {
-                seen.remove(&current);
+                seen.remove(current.0, current.1);
             }
         }
     }
@@ -1,11 +1,9 @@
 use std::fmt::Display;
 
-use aoc2023::util::get_all_numbers;
+use aoc2023::{linalg, util::get_all_numbers};
 use core::ops::RangeInclusive;
 use log::info;
-use num::{bigint::ToBigInt, BigInt};
-
-use vector3d::Vector3d;
+use num::BigRational;
 
 aoc2023::solver!(part1, part2);
 
@@ -101,26 +99,55 @@ impl Path {
         return false;
     }
 
-    fn to_stone(&self) -> Stone {
-        let p = Vector3d::new(
-            self.x.position.to_bigint().unwrap(),
-            self.y.position.to_bigint().unwrap(),
-            self.z.position.to_bigint().unwrap(),
-        );
+    fn position(&self) -> [i64; 3] {
+        [self.x.position, self.y.position, self.z.position]
+    }
+
+    fn velocity(&self) -> [i64; 3] {
+        [self.x.velocity, self.y.velocity, self.z.velocity]
+    }
+
+    // Equations for the rock position and velocity derived from this path and
+    // another. Unknowns are ordered px, py, pz, vx, vy, vz.
+    fn rock_equations(&self, other: &Self) -> (Vec<Vec<BigRational>>, Vec<BigRational>) {
+        let (p_i, v_i) = (self.position(), self.velocity());
+        let (p_j, v_j) = (other.position(), other.velocity());
 
-        let v = Vector3d::new(
-            self.x.velocity.to_bigint().unwrap(),
-            self.y.velocity.to_bigint().unwrap(),
-            self.z.velocity.to_bigint().unwrap(),
-        );
+        let d: Vec<_> = (0..3).map(|k| v_j[k] - v_i[k]).collect();
+        let e: Vec<_> = (0..3).map(|k| p_j[k] - p_i[k]).collect();
 
-        return Stone { p, v };
+        let rows = [
+            [0, d[2], -d[1], 0, -e[2], e[1]],
+            [-d[2], 0, d[0], e[2], 0, -e[0]],
+            [d[1], -d[0], 0, -e[1], e[0], 0],
+        ];
+
+        let c_i = cross(&p_i, &v_i);
+        let c_j = cross(&p_j, &v_j);
+
+        let matrix = rows
+            .iter()
+            .map(|r| r.iter().map(|v| to_rational(*v as i128)).collect())
+            .collect();
+        let rhs = (0..3).map(|k| to_rational(c_j[k] - c_i[k])).collect();
+
+        (matrix, rhs)
     }
 }
 
-struct Stone {
-    p: Vector3d<BigInt>,
-    v: Vector3d<BigInt>,
+fn cross(a: &[i64; 3], b: &[i64; 3]) -> [i128; 3] {
+    let a = a.map(|v| v as i128);
+    let b = b.map(|v| v as i128);
+
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn to_rational(value: i128) -> BigRational {
+    BigRational::from_integer(value.into())
 }
 
 fn find_intersection(a1: &Line, b1: &Line, a2: &Line, b2: &Line) -> Option<f64> {
@@ -164,55 +191,39 @@ fn part1(lines: &[String]) -> String {
 /*
  * Needed a hint for this one.
  *
- * Implementation inspired by:
- * https://old.reddit.com/r/adventofcode/comments/18qexvu/2023_day_24_part_2_3d_vector_interpretation_and/
+ * The rock at position P with velocity V hits every hailstone i, so
+ * (P - p_i) x (V - v_i) = 0. Expanding gives a P x V term that is the same for
+ * every hailstone, so subtracting the equations for two hailstones leaves a
+ * system that is linear in P and V.
+ *
+ *   P x (v_j - v_i) + (p_j - p_i) x V = p_j x v_j - p_i x v_i
+ *
+ * Each pair of hailstones gives three equations. Use a few pairs so that any
+ * parallel hailstones still leave enough independent equations.
  */
 fn part2(lines: &[String]) -> String {
-    let stones: Vec<_> = lines
-        .iter()
-        .map(Path::parse)
-        .map(|p| p.to_stone())
-        .collect();
-
-    let p0 = stones[0].p.clone();
-    let v0 = stones[0].v.clone();
-
-    // Shift all stones into a reference frame based on stone 0. Stone 0 is now
-    // effectively stationary.
-    let shifted: Vec<_> = stones
-        .iter()
-        .map(|s| Stone {
-            p: s.p.clone() - p0.clone(),
-            v: s.v.clone() - v0.clone(),
-        })
-        .collect();
-
-    // Find normal line for stone 1.
-    let h1 = &shifted[1];
-    let h1_p0 = &h1.p;
-    let h1_p1 = h1.p.clone() + h1.v.clone();
-    let n = h1_p0.clone().cross(h1_p1);
-
-    // Find intersection time and position for stone 2
-    let h2 = &shifted[2];
-    let t_h2 = (shifted[0].p.clone() - h2.p.clone()).dot(n.clone()) / h2.v.clone().dot(n.clone());
-    let p_h2 = h2.p.clone() + (h2.v.clone() * t_h2.clone());
-
-    // Find intersection time and position for stone 3
-    let h3 = &shifted[3];
-    let t_h3 = (shifted[0].p.clone() - h3.p.clone()).dot(n.clone()) / h3.v.clone().dot(n);
-    let p_h3 = h3.p.clone() + (h3.v.clone() * t_h3.clone());
-
-    // Calculate velocity and position of rock in the shifted reference frame.
-    let velo = (p_h3 - p_h2.clone()) / (t_h3.clone() - t_h2.clone());
-    let position = p_h2.clone() - (velo.clone() * t_h2);
-    info!("Shifted velo {}, Shifted position {}", velo, position);
-
-    // Unshift
-    let real_position = position + stones[0].p.clone();
-    info!("Real position is {}", real_position);
-
-    let total = real_position.x + real_position.y + real_position.z;
+    let paths: Vec<_> = lines.iter().map(Path::parse).collect();
+
+    let mut matrix = Vec::new();
+    let mut rhs = Vec::new();
+
+    for other in &paths[1..4] {
+        let (rows, values) = paths[0].rock_equations(other);
+        matrix.extend(rows);
+        rhs.extend(values);
+    }
+
+    let solution = linalg::solve(&matrix, &rhs)
+        .unwrap_or_else(|e| panic!("Failed to find rock trajectory: {}", e));
+    info!(
+        "Rock position {}, {}, {} velocity {}, {}, {}",
+        solution[0], solution[1], solution[2], solution[3], solution[4], solution[5]
+    );
+
+    let total: BigRational = solution[..3].iter().sum();
+    if !total.is_integer() {
+        panic!("Rock position is not an integer: {}", total);
+    }
 
     format!("{}", total)
 }
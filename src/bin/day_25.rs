@@ -1,9 +1,13 @@
 use std::{
     collections::{HashMap, HashSet},
+    sync::atomic::{AtomicUsize, Ordering},
     time::SystemTime,
 };
 
-use aoc2023::util::combinations_3;
+use aoc2023::{
+    collections::disjoint_set::DisjointSet, graph::dot::Dot, solver::output_path,
+    util::par_index_combinations,
+};
 use log::info;
 
 use rayon::prelude::*;
@@ -59,7 +63,8 @@ fn part1(lines: &[String]) -> String {
 
     let graph = Graph::new(connections);
 
-    let (a, b) = try_partion(&graph);
+    let (a, b, cut) = try_partion(&graph);
+    write_dot(&graph, &cut);
 
     let total = a * b;
 
@@ -74,19 +79,35 @@ fn parse(lines: &[String]) -> impl Iterator<Item = Connection> {
     })
 }
 
-fn try_partion<'a>(graph: &Graph<'a>) -> (usize, usize) {
-    let start = SystemTime::now();
+// With --dot <file>, write the graph with the cut connections highlighted.
+fn write_dot(graph: &Graph, cut: &[usize]) {
+    let Some(path) = output_path("dot") else {
+        return;
+    };
+
+    let mut dot = Dot::graph("day_25");
+    for (i, c) in graph.connections.iter().enumerate() {
+        if cut.contains(&i) {
+            dot.edge(c.0, c.1, &[("color", "red"), ("penwidth", "3")]);
+        } else {
+            dot.edge(c.0, c.1, &[]);
+        }
+    }
 
-    let connection_idx: Vec<_> = (0..graph.connections.len()).collect();
+    info!("Writing graph to {}", path);
+    dot.write_file(&path)
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", path, e));
+}
 
-    let result = combinations_3(&connection_idx)
-        .enumerate()
-        .par_bridge()
-        .find_map_first(|(i, (a, b, c))| {
-            let mut visited = vec![false; graph.nodes.len()];
+// Returns the size of both partitions and the connections that were cut.
+fn try_partion<'a>(graph: &Graph<'a>) -> (usize, usize, [usize; 3]) {
+    let start = SystemTime::now();
 
-            let mut to_visit = vec![0];
+    let iterations = AtomicUsize::new(0);
 
+    let result = par_index_combinations::<3>(graph.connections.len())
+        .find_map_first(|[a, b, c]| {
+            let i = iterations.fetch_add(1, Ordering::Relaxed);
             if i % 100000 == 0 {
                 let duration = SystemTime::now()
                     .duration_since(start)
@@ -96,27 +117,22 @@ fn try_partion<'a>(graph: &Graph<'a>) -> (usize, usize) {
                 info!("Iteration {}, {:.2} iterations per second", i, ips);
             }
 
-            // Visit every node connected to the start node.
-            while !to_visit.is_empty() {
-                let current = to_visit.pop().unwrap();
-                visited[current] = true;
-
-                graph.nodes[current].iter().for_each(|link| {
-                    if link.connection != *a
-                        && link.connection != *b
-                        && link.connection != *c
-                        && !visited[link.node]
-                    {
-                        to_visit.push(link.node);
-                    }
-                })
+            // Join every node still linked after removing the connections.
+            let mut components = DisjointSet::new(graph.nodes.len());
+            for (node, links) in graph.nodes.iter().enumerate() {
+                links
+                    .iter()
+                    .filter(|link| ![a, b, c].contains(&link.connection))
+                    .for_each(|link| {
+                        components.union(node, link.node);
+                    });
             }
 
-            let connected = visited.iter().filter(|&v| *v).count();
-            if connected != visited.len() {
-                let remaining = visited.len() - connected;
+            if components.count() > 1 {
+                let connected = components.component_size(0);
+                let remaining = graph.nodes.len() - connected;
                 info!("Found two partitions {}, {}", connected, remaining);
-                return Some((connected, remaining));
+                return Some((connected, remaining, [a, b, c]));
             }
 
             None
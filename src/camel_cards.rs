@@ -0,0 +1,432 @@
+use std::{cmp::Ordering, fmt::Display};
+
+// A kind of hand, made by having groups of matching cards at least as large
+// as the pattern. [3, 2] is a full house, [2] is any hand with a pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandKind {
+    pub name: String,
+    pub pattern: Vec<usize>,
+}
+
+impl HandKind {
+    pub fn new(name: &str, pattern: &[usize]) -> Self {
+        let mut pattern = pattern.to_vec();
+        pattern.sort_by(|a, b| b.cmp(a));
+
+        Self {
+            name: name.to_string(),
+            pattern,
+        }
+    }
+
+    // Poker style kinds from weakest to strongest. Hands larger than five
+    // cards also get a kind for each larger group size.
+    pub fn standard(hand_size: usize) -> Vec<HandKind> {
+        let mut kinds = vec![
+            HandKind::new("High card", &[]),
+            HandKind::new("One pair", &[2]),
+            HandKind::new("Two pair", &[2, 2]),
+            HandKind::new("Three of a kind", &[3]),
+            HandKind::new("Full house", &[3, 2]),
+            HandKind::new("Four of a kind", &[4]),
+            HandKind::new("Five of a kind", &[5]),
+        ];
+
+        for n in 6..=hand_size {
+            kinds.push(HandKind::new(&format!("{} of a kind", n), &[n]));
+        }
+
+        kinds
+    }
+
+    // Number of wildcards needed to reach the pattern, given the sizes of the
+    // groups of natural cards in descending order. Pairing the largest groups
+    // with the largest parts of the pattern needs the fewest.
+    fn wildcards_needed(&self, groups: &[usize]) -> usize {
+        self.pattern
+            .iter()
+            .enumerate()
+            .map(|(i, p)| p.saturating_sub(*groups.get(i).unwrap_or(&0)))
+            .sum()
+    }
+}
+
+// How hands of the same kind are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    // Compare cards in the order they were dealt.
+    DealtOrder,
+
+    // Compare cards from highest to lowest rank, as in poker.
+    HighestFirst,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rules {
+    // Ranks as given, and with any wildcards moved to the bottom.
+    natural: Vec<char>,
+    ranks: Vec<char>,
+    wildcards: Vec<char>,
+    hand_size: usize,
+    kinds: Vec<HandKind>,
+    tie_break: TieBreak,
+}
+
+impl Rules {
+    // Ranks are listed from lowest to highest.
+    pub fn new(ranks: &str, hand_size: usize) -> Self {
+        Self {
+            natural: ranks.chars().collect(),
+            ranks: ranks.chars().collect(),
+            wildcards: Vec::new(),
+            hand_size,
+            kinds: HandKind::standard(hand_size),
+            tie_break: TieBreak::DealtOrder,
+        }
+    }
+
+    // Day 7 part one.
+    pub fn standard() -> Self {
+        Self::new("23456789TJQKA", 5)
+    }
+
+    // Day 7 part two, jokers are wild and the lowest rank.
+    pub fn jokers_wild() -> Self {
+        Self::standard().with_wildcards("J")
+    }
+
+    // Wildcards count as whatever card makes the best hand, and rank below
+    // every other card when breaking ties. Replaces any earlier wildcards.
+    pub fn with_wildcards(mut self, wildcards: &str) -> Self {
+        self.wildcards = wildcards.chars().collect();
+        let (mut wild, natural): (Vec<_>, Vec<_>) = self
+            .natural
+            .iter()
+            .partition(|c| self.wildcards.contains(c));
+        wild.extend(natural);
+        self.ranks = wild;
+        self
+    }
+
+    // Replace the ranks, listed from lowest to highest. Wildcards stay wild
+    // and still rank lowest.
+    pub fn with_ranks(mut self, ranks: &str) -> Self {
+        self.natural = ranks.chars().collect();
+        let wildcards: String = self.wildcards.iter().collect();
+        self.with_wildcards(&wildcards)
+    }
+
+    // Change the number of cards in a hand, with the standard kinds for that
+    // size.
+    pub fn with_hand_size(mut self, hand_size: usize) -> Self {
+        self.hand_size = hand_size;
+        self.kinds = HandKind::standard(hand_size);
+        self
+    }
+
+    // Kinds are listed from weakest to strongest. A hand takes the strongest
+    // kind it can make, so the weakest should have an empty pattern.
+    pub fn with_kinds(mut self, kinds: Vec<HandKind>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn hand_size(&self) -> usize {
+        self.hand_size
+    }
+
+    pub fn kinds(&self) -> &[HandKind] {
+        &self.kinds
+    }
+
+    fn rank_of(&self, card: char) -> usize {
+        self.ranks
+            .iter()
+            .position(|c| *c == card)
+            .unwrap_or_else(|| panic!("Unknown card: {}", card))
+    }
+
+    // Index into kinds of the strongest kind the cards make.
+    pub fn classify(&self, cards: &[char]) -> usize {
+        if cards.len() != self.hand_size {
+            panic!("Expected {} cards, found {}", self.hand_size, cards.len());
+        }
+
+        let mut counts = vec![0; self.ranks.len()];
+        let mut wild = 0;
+        for c in cards {
+            if self.wildcards.contains(c) {
+                wild += 1;
+            } else {
+                counts[self.rank_of(*c)] += 1;
+            }
+        }
+
+        let mut groups: Vec<_> = counts.into_iter().filter(|c| *c > 0).collect();
+        groups.sort_by(|a, b| b.cmp(a));
+
+        (0..self.kinds.len())
+            .rev()
+            .find(|k| {
+                let kind = &self.kinds[*k];
+                kind.pattern.iter().sum::<usize>() <= self.hand_size
+                    && kind.wildcards_needed(&groups) <= wild
+            })
+            .unwrap_or_else(|| panic!("No hand kind matches {:?}", cards))
+    }
+
+    fn sort_key(&self, cards: &[char]) -> (usize, Vec<usize>) {
+        let mut values: Vec<_> = cards.iter().map(|c| self.rank_of(*c)).collect();
+        if self.tie_break == TieBreak::HighestFirst {
+            values.sort_by(|a, b| b.cmp(a));
+        }
+
+        (self.classify(cards), values)
+    }
+
+    pub fn compare(&self, a: &[char], b: &[char]) -> Ordering {
+        self.sort_key(a).cmp(&self.sort_key(b))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hand {
+    pub cards: Vec<char>,
+    pub bid: u64,
+}
+
+impl Hand {
+    // Cards followed by the bid, e.g. "32T3K 765".
+    pub fn parse(line: &str) -> Self {
+        let (cards, bid) = line
+            .split_once(' ')
+            .unwrap_or_else(|| panic!("Invalid hand: {}", line));
+
+        Self {
+            cards: cards.chars().collect(),
+            bid: bid
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid bid: {}", line)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankedHand {
+    pub hand: Hand,
+    pub kind: String,
+    pub rank: usize,
+}
+
+impl RankedHand {
+    pub fn winnings(&self) -> u64 {
+        self.hand.bid * self.rank as u64
+    }
+}
+
+impl Display for RankedHand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cards: String = self.hand.cards.iter().collect();
+        write!(
+            f,
+            "{:>5} {} {:<16} bid {} x rank {} = {}",
+            self.rank,
+            cards,
+            self.kind,
+            self.hand.bid,
+            self.rank,
+            self.winnings()
+        )
+    }
+}
+
+// Hands from weakest to strongest, with rank 1 the weakest. Hands that tie
+// keep their original order.
+pub fn rank_hands(rules: &Rules, hands: &[Hand]) -> Vec<RankedHand> {
+    let mut keyed: Vec<_> = hands
+        .iter()
+        .map(|h| (rules.sort_key(&h.cards), h))
+        .collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+
+    keyed
+        .into_iter()
+        .enumerate()
+        .map(|(i, ((kind, _), hand))| RankedHand {
+            hand: hand.clone(),
+            kind: rules.kinds[kind].name.clone(),
+            rank: i + 1,
+        })
+        .collect()
+}
+
+pub fn total_winnings(ranked: &[RankedHand]) -> u64 {
+    ranked.iter().map(|r| r.winnings()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Hand> {
+        [
+            "32T3K 765",
+            "T55J5 684",
+            "KK677 28",
+            "KTJJT 220",
+            "QQQJA 483",
+        ]
+        .iter()
+        .map(|l| Hand::parse(l))
+        .collect()
+    }
+
+    fn kind_name(rules: &Rules, cards: &str) -> String {
+        let cards: Vec<_> = cards.chars().collect();
+        rules.kinds()[rules.classify(&cards)].name.clone()
+    }
+
+    #[test]
+    fn test_classify_standard() {
+        let rules = Rules::standard();
+
+        assert_eq!(kind_name(&rules, "AAAAA"), "Five of a kind");
+        assert_eq!(kind_name(&rules, "AA8AA"), "Four of a kind");
+        assert_eq!(kind_name(&rules, "23332"), "Full house");
+        assert_eq!(kind_name(&rules, "TTT98"), "Three of a kind");
+        assert_eq!(kind_name(&rules, "23432"), "Two pair");
+        assert_eq!(kind_name(&rules, "A23A4"), "One pair");
+        assert_eq!(kind_name(&rules, "23456"), "High card");
+    }
+
+    #[test]
+    fn test_classify_wild() {
+        let rules = Rules::jokers_wild();
+
+        assert_eq!(kind_name(&rules, "JJJJJ"), "Five of a kind");
+        assert_eq!(kind_name(&rules, "QJJQ2"), "Four of a kind");
+        assert_eq!(kind_name(&rules, "2233J"), "Full house");
+        assert_eq!(kind_name(&rules, "T55J5"), "Four of a kind");
+        assert_eq!(kind_name(&rules, "2345J"), "One pair");
+    }
+
+    #[test]
+    fn test_classify_multiple_wildcards() {
+        let rules = Rules::standard().with_wildcards("J2");
+
+        assert_eq!(kind_name(&rules, "2J345"), "Three of a kind");
+        assert_eq!(kind_name(&rules, "2J334"), "Four of a kind");
+    }
+
+    #[test]
+    fn test_classify_six_cards() {
+        let rules = Rules::new("23456789TJQKA", 6);
+
+        assert_eq!(kind_name(&rules, "333222"), "Full house");
+        assert_eq!(kind_name(&rules, "AAAAAA"), "6 of a kind");
+        assert_eq!(kind_name(&rules, "223344"), "Two pair");
+    }
+
+    #[test]
+    fn test_custom_kinds() {
+        let kinds = vec![
+            HandKind::new("Nothing", &[]),
+            HandKind::new("Three pair", &[2, 2, 2]),
+        ];
+        let rules = Rules::new("23456789TJQKA", 6).with_kinds(kinds);
+
+        assert_eq!(kind_name(&rules, "223344"), "Three pair");
+        assert_eq!(kind_name(&rules, "222234"), "Nothing");
+    }
+
+    #[test]
+    fn test_replace_wildcards() {
+        let rules = Rules::jokers_wild().with_wildcards("Q");
+        let a: Vec<_> = "JJ234".chars().collect();
+        let b: Vec<_> = "TT234".chars().collect();
+
+        assert_eq!(kind_name(&rules, "JJ234"), "One pair");
+        assert_eq!(kind_name(&rules, "QQ234"), "Three of a kind");
+        assert_eq!(rules.compare(&a, &b), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_with_ranks_keeps_wildcards() {
+        // Reversed, so aces are low.
+        let rules = Rules::jokers_wild().with_ranks("AKQJT98765432");
+        let cards = |c: &str| c.chars().collect::<Vec<_>>();
+
+        assert_eq!(kind_name(&rules, "J2345"), "One pair");
+        assert_eq!(
+            rules.compare(&cards("J2222"), &cards("22222")),
+            Ordering::Less
+        );
+        assert_eq!(
+            rules.compare(&cards("A3333"), &cards("23333")),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_with_hand_size() {
+        let rules = Rules::jokers_wild().with_hand_size(6);
+
+        assert_eq!(rules.hand_size(), 6);
+        assert_eq!(kind_name(&rules, "JAAAAA"), "6 of a kind");
+    }
+
+    #[test]
+    fn test_wildcards_rank_lowest() {
+        let rules = Rules::jokers_wild();
+        let a: Vec<_> = "JKKK2".chars().collect();
+        let b: Vec<_> = "QQQQ2".chars().collect();
+
+        assert_eq!(rules.compare(&a, &b), Ordering::Less);
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let a: Vec<_> = "2AKQT".chars().collect();
+        let b: Vec<_> = "A2345".chars().collect();
+
+        let dealt = Rules::standard();
+        assert_eq!(dealt.compare(&a, &b), Ordering::Less);
+
+        let highest = Rules::standard().with_tie_break(TieBreak::HighestFirst);
+        assert_eq!(highest.compare(&a, &b), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_rank_hands() {
+        let ranked = rank_hands(&Rules::standard(), &example());
+        let order: Vec<String> = ranked
+            .iter()
+            .map(|r| r.hand.cards.iter().collect())
+            .collect();
+
+        assert_eq!(order, vec!["32T3K", "KTJJT", "KK677", "T55J5", "QQQJA"]);
+        assert_eq!(total_winnings(&ranked), 6440);
+        assert_eq!(
+            ranked[0].to_string(),
+            "    1 32T3K One pair         bid 765 x rank 1 = 765"
+        );
+    }
+
+    #[test]
+    fn test_rank_hands_wild() {
+        let ranked = rank_hands(&Rules::jokers_wild(), &example());
+        assert_eq!(total_winnings(&ranked), 5905);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wrong_hand_size() {
+        Rules::standard().classify(&['A', 'K']);
+    }
+}
@@ -1 +1,4 @@
+pub mod bit_grid;
+pub mod bitset;
+pub mod disjoint_set;
 pub mod grid;
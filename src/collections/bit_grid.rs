@@ -0,0 +1,185 @@
+use super::{bitset::BitSet, grid::Grid};
+
+// Dense grid of booleans, with each row stored as a bit set so whole rows can
+// be combined or shifted at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    rows: Vec<BitSet>,
+    cols: usize,
+}
+
+impl BitGrid {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows: vec![BitSet::new(cols); rows],
+            cols,
+        }
+    }
+
+    pub fn from_grid<T, P>(grid: &Grid<T>, predicate: P) -> Self
+    where
+        P: Fn(&T) -> bool,
+    {
+        let mut result = Self::new(grid.rows(), grid.cols());
+        grid.enumerate()
+            .filter(|(_, _, v)| predicate(v))
+            .for_each(|(i, j, _)| {
+                result.insert(i, j);
+            });
+        result
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, i: usize) -> &BitSet {
+        &self.rows[i]
+    }
+
+    pub fn row_mut(&mut self, i: usize) -> &mut BitSet {
+        &mut self.rows[i]
+    }
+
+    pub fn contains(&self, i: usize, j: usize) -> bool {
+        self.rows[i].contains(j)
+    }
+
+    pub fn insert(&mut self, i: usize, j: usize) -> bool {
+        self.rows[i].insert(j)
+    }
+
+    pub fn remove(&mut self, i: usize, j: usize) -> bool {
+        self.rows[i].remove(j)
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.iter_mut().for_each(|r| r.clear());
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.rows.iter().map(|r| r.count_ones()).sum()
+    }
+
+    pub fn union_with(&mut self, other: &BitGrid) {
+        self.check_size(other);
+        self.rows
+            .iter_mut()
+            .zip(&other.rows)
+            .for_each(|(a, b)| a.union_with(b));
+    }
+
+    pub fn intersect_with(&mut self, other: &BitGrid) {
+        self.check_size(other);
+        self.rows
+            .iter_mut()
+            .zip(&other.rows)
+            .for_each(|(a, b)| a.intersect_with(b));
+    }
+
+    // Move every cell by the given number of rows and columns. Cells moved
+    // outside the grid are dropped.
+    pub fn shift(&self, rows: isize, cols: isize) -> BitGrid {
+        let mut result = Self::new(self.rows(), self.cols);
+
+        for (i, row) in self.rows.iter().enumerate() {
+            let target = i as isize + rows;
+            if target < 0 || target >= self.rows() as isize {
+                continue;
+            }
+
+            result.rows[target as usize] = if cols >= 0 {
+                row.shift_up(cols as usize)
+            } else {
+                row.shift_down(cols.unsigned_abs())
+            };
+        }
+
+        result
+    }
+
+    // Cells that are a single horizontal or vertical step away from a set
+    // cell.
+    pub fn neighbors(&self) -> BitGrid {
+        let mut result = self.shift(-1, 0);
+        result.union_with(&self.shift(1, 0));
+        result.union_with(&self.shift(0, -1));
+        result.union_with(&self.shift(0, 1));
+        result
+    }
+
+    fn check_size(&self, other: &BitGrid) {
+        if self.rows() != other.rows() || self.cols != other.cols {
+            panic!(
+                "Bit grid sizes differ: {} x {} and {} x {}",
+                self.rows(),
+                self.cols,
+                other.rows(),
+                other.cols
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let mut grid = BitGrid::new(3, 100);
+        assert!(grid.insert(1, 99));
+        assert!(!grid.insert(1, 99));
+        assert!(grid.contains(1, 99));
+        assert!(!grid.contains(0, 99));
+        assert_eq!(grid.count_ones(), 1);
+
+        assert!(grid.remove(1, 99));
+        assert_eq!(grid.count_ones(), 0);
+    }
+
+    #[test]
+    fn test_from_grid() {
+        let lines = ["#..", ".#.", "..#"];
+        let grid: Grid<_> = lines.iter().map(|l| l.chars()).collect();
+        let bits = BitGrid::from_grid(&grid, |c| *c == '#');
+
+        assert_eq!(bits.count_ones(), 3);
+        assert!(bits.contains(0, 0));
+        assert!(bits.contains(1, 1));
+        assert!(bits.contains(2, 2));
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut grid = BitGrid::new(3, 3);
+        grid.insert(0, 0);
+        grid.insert(2, 2);
+
+        let shifted = grid.shift(1, 1);
+        assert!(shifted.contains(1, 1));
+        assert_eq!(shifted.count_ones(), 1);
+
+        let shifted = grid.shift(-2, -1);
+        assert!(shifted.contains(0, 1));
+        assert_eq!(shifted.count_ones(), 1);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let mut grid = BitGrid::new(3, 3);
+        grid.insert(1, 1);
+
+        let neighbors = grid.neighbors();
+        assert_eq!(neighbors.count_ones(), 4);
+        assert!(neighbors.contains(0, 1));
+        assert!(neighbors.contains(1, 0));
+        assert!(neighbors.contains(1, 2));
+        assert!(neighbors.contains(2, 1));
+        assert!(!neighbors.contains(1, 1));
+    }
+}
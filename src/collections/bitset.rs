@@ -0,0 +1,248 @@
+const WORD_BITS: usize = u64::BITS as usize;
+
+// Fixed size set of indices in 0..len, stored one bit per index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(WORD_BITS)],
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.check_index(i);
+        self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    // Returns true if the index was not already in the set.
+    pub fn insert(&mut self, i: usize) -> bool {
+        let present = self.contains(i);
+        self.words[i / WORD_BITS] |= 1 << (i % WORD_BITS);
+        !present
+    }
+
+    // Returns true if the index was in the set.
+    pub fn remove(&mut self, i: usize) -> bool {
+        let present = self.contains(i);
+        self.words[i / WORD_BITS] &= !(1 << (i % WORD_BITS));
+        present
+    }
+
+    pub fn clear(&mut self) {
+        self.words.iter_mut().for_each(|w| *w = 0);
+    }
+
+    pub fn fill(&mut self) {
+        self.words.iter_mut().for_each(|w| *w = u64::MAX);
+        self.trim();
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, w)| {
+            let mut word = *w;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * WORD_BITS + bit)
+            })
+        })
+    }
+
+    pub fn union_with(&mut self, other: &BitSet) {
+        self.check_len(other);
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    pub fn intersect_with(&mut self, other: &BitSet) {
+        self.check_len(other);
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a &= b);
+    }
+
+    pub fn difference_with(&mut self, other: &BitSet) {
+        self.check_len(other);
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a &= !b);
+    }
+
+    // Move every index i to i + n. Indices shifted past the end are dropped.
+    pub fn shift_up(&self, n: usize) -> BitSet {
+        let mut result = BitSet::new(self.len);
+        let (word_shift, bit_shift) = (n / WORD_BITS, n % WORD_BITS);
+
+        for i in word_shift..self.words.len() {
+            let src = i - word_shift;
+            let mut value = self.words[src] << bit_shift;
+            if bit_shift > 0 && src > 0 {
+                value |= self.words[src - 1] >> (WORD_BITS - bit_shift);
+            }
+            result.words[i] = value;
+        }
+
+        result.trim();
+        result
+    }
+
+    // Move every index i to i - n. Indices shifted below zero are dropped.
+    pub fn shift_down(&self, n: usize) -> BitSet {
+        let mut result = BitSet::new(self.len);
+        let (word_shift, bit_shift) = (n / WORD_BITS, n % WORD_BITS);
+
+        for i in 0..self.words.len().saturating_sub(word_shift) {
+            let src = i + word_shift;
+            let mut value = self.words[src] >> bit_shift;
+            if bit_shift > 0 && src + 1 < self.words.len() {
+                value |= self.words[src + 1] << (WORD_BITS - bit_shift);
+            }
+            result.words[i] = value;
+        }
+
+        result
+    }
+
+    // Clear any bits in the last word beyond the length of the set.
+    fn trim(&mut self) {
+        let extra = self.len % WORD_BITS;
+        if extra > 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << extra) - 1;
+            }
+        }
+    }
+
+    fn check_index(&self, i: usize) {
+        if i >= self.len {
+            panic!(
+                "Index {} out of range for bit set of length {}",
+                i, self.len
+            );
+        }
+    }
+
+    fn check_len(&self, other: &BitSet) {
+        if self.len != other.len {
+            panic!("Bit set lengths differ: {} and {}", self.len, other.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_indices(len: usize, indices: &[usize]) -> BitSet {
+        let mut set = BitSet::new(len);
+        indices.iter().for_each(|i| {
+            set.insert(*i);
+        });
+        set
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = BitSet::new(130);
+        assert!(set.insert(0));
+        assert!(set.insert(129));
+        assert!(!set.insert(129));
+
+        assert!(set.contains(0));
+        assert!(set.contains(129));
+        assert!(!set.contains(64));
+        assert_eq!(set.count_ones(), 2);
+
+        assert!(set.remove(0));
+        assert!(!set.remove(0));
+        assert_eq!(set.count_ones(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_range() {
+        let set = BitSet::new(10);
+        set.contains(10);
+    }
+
+    #[test]
+    fn test_iter() {
+        let set = from_indices(200, &[3, 64, 65, 199]);
+        let values: Vec<_> = set.iter().collect();
+
+        assert_eq!(values, vec![3, 64, 65, 199]);
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut set = BitSet::new(70);
+        set.fill();
+
+        assert_eq!(set.count_ones(), 70);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = from_indices(100, &[1, 2, 70]);
+        let b = from_indices(100, &[2, 3, 70, 99]);
+
+        let mut union = a.clone();
+        union.union_with(&b);
+        assert_eq!(union.iter().collect::<Vec<_>>(), vec![1, 2, 3, 70, 99]);
+
+        let mut intersection = a.clone();
+        intersection.intersect_with(&b);
+        assert_eq!(intersection.iter().collect::<Vec<_>>(), vec![2, 70]);
+
+        let mut difference = a.clone();
+        difference.difference_with(&b);
+        assert_eq!(difference.iter().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_shift_up() {
+        let set = from_indices(130, &[0, 63, 100, 129]);
+
+        let shifted = set.shift_up(1);
+        assert_eq!(shifted.iter().collect::<Vec<_>>(), vec![1, 64, 101]);
+
+        let shifted = set.shift_up(65);
+        assert_eq!(shifted.iter().collect::<Vec<_>>(), vec![65, 128]);
+    }
+
+    #[test]
+    fn test_shift_down() {
+        let set = from_indices(130, &[0, 64, 100, 129]);
+
+        let shifted = set.shift_down(1);
+        assert_eq!(shifted.iter().collect::<Vec<_>>(), vec![63, 99, 128]);
+
+        let shifted = set.shift_down(65);
+        assert_eq!(shifted.iter().collect::<Vec<_>>(), vec![35, 64]);
+    }
+}
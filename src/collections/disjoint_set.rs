@@ -0,0 +1,154 @@
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<usize>,
+    size: Vec<usize>,
+    count: usize,
+}
+
+impl DisjointSet {
+    pub fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+            size: vec![1; n],
+            count: n,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    // Number of disjoint components.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // Point everything on the path directly at the root.
+        let mut current = x;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    // Merge the components containing a and b. Returns false if they were
+    // already in the same component.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+
+        if a == b {
+            return false;
+        }
+
+        let (root, child) = if self.rank[a] < self.rank[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+
+        if self.rank[root] == self.rank[child] {
+            self.rank[root] += 1;
+        }
+
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+        self.count -= 1;
+
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    pub fn component_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    // Members of every component. Components are ordered by their smallest
+    // member, and members are in ascending order.
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut index = vec![usize::MAX; self.len()];
+        let mut result: Vec<Vec<usize>> = Vec::new();
+
+        for x in 0..self.len() {
+            let root = self.find(x);
+            if index[root] == usize::MAX {
+                index[root] = result.len();
+                result.push(Vec::new());
+            }
+            result[index[root]].push(x);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let mut set = DisjointSet::new(4);
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.count(), 4);
+        assert!(!set.connected(0, 1));
+        assert_eq!(set.component_size(2), 1);
+    }
+
+    #[test]
+    fn test_union() {
+        let mut set = DisjointSet::new(5);
+        assert!(set.union(0, 1));
+        assert!(set.union(3, 4));
+        assert!(set.union(1, 4));
+        assert!(!set.union(0, 3));
+
+        assert_eq!(set.count(), 2);
+        assert!(set.connected(0, 3));
+        assert!(!set.connected(0, 2));
+        assert_eq!(set.component_size(4), 4);
+        assert_eq!(set.component_size(2), 1);
+    }
+
+    #[test]
+    fn test_components() {
+        let mut set = DisjointSet::new(6);
+        set.union(5, 1);
+        set.union(2, 4);
+        set.union(4, 0);
+
+        assert_eq!(set.components(), vec![vec![0, 2, 4], vec![1, 5], vec![3]]);
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let n = 100000;
+        let mut set = DisjointSet::new(n);
+        for i in 1..n {
+            set.union(i - 1, i);
+        }
+
+        assert_eq!(set.count(), 1);
+        assert_eq!(set.component_size(0), n);
+        assert!(set.connected(0, n - 1));
+    }
+}
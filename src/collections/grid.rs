@@ -1,3 +1,5 @@
+use super::disjoint_set::DisjointSet;
+
 #[derive(Debug)]
 pub struct Grid<T> {
     values: Vec<Vec<T>>,
@@ -91,6 +93,36 @@ impl<T> Grid<T> {
             .rev()
             .for_each(|v| v.insert(i, new_col.pop().unwrap()));
     }
+
+    // Groups of horizontally or vertically adjacent cells matching the
+    // predicate. Cells that do not match are not part of any group.
+    pub fn connected_components<P>(&self, predicate: P) -> Vec<Vec<(usize, usize)>>
+    where
+        P: Fn(&T) -> bool,
+    {
+        let cols = self.cols();
+        let mut set = DisjointSet::new(self.rows() * cols);
+
+        for (i, j, v) in self.enumerate() {
+            if !predicate(v) {
+                continue;
+            }
+
+            if i + 1 < self.rows() && predicate(self.get(i + 1, j)) {
+                set.union(i * cols + j, (i + 1) * cols + j);
+            }
+
+            if j + 1 < cols && predicate(self.get(i, j + 1)) {
+                set.union(i * cols + j, i * cols + j + 1);
+            }
+        }
+
+        set.components()
+            .into_iter()
+            .filter(|c| predicate(self.get(c[0] / cols, c[0] % cols)))
+            .map(|c| c.into_iter().map(|x| (x / cols, x % cols)).collect())
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -179,4 +211,17 @@ mod tests {
 
         assert_eq!(*grid.get(0, 1), '1');
     }
+
+    #[test]
+    fn test_connected_components() {
+        let lines = ["aab", "bab", "bba", "aab"];
+        let grid: Grid<_> = lines.iter().map(|l| l.chars()).collect();
+
+        let components = grid.connected_components(|c| *c == 'a');
+
+        assert_eq!(components.len(), 3);
+        assert_eq!(components[0], vec![(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(components[1], vec![(2, 2)]);
+        assert_eq!(components[2], vec![(3, 0), (3, 1)]);
+    }
 }
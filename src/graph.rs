@@ -0,0 +1,3 @@
+pub mod dag;
+pub mod digraph;
+pub mod dot;
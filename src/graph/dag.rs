@@ -0,0 +1,249 @@
+use std::collections::VecDeque;
+
+// Directed acyclic graph over nodes 0..n. Acyclicity is not checked when
+// edges are added, topological_order returns None if a cycle exists.
+#[derive(Debug, Clone, Default)]
+pub struct Dag {
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl Dag {
+    pub fn new(n: usize) -> Self {
+        Self {
+            successors: vec![Vec::new(); n],
+            predecessors: vec![Vec::new(); n],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.successors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.successors.is_empty()
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.successors[from].push(to);
+        self.predecessors[to].push(from);
+    }
+
+    pub fn successors(&self, node: usize) -> &[usize] {
+        &self.successors[node]
+    }
+
+    pub fn predecessors(&self, node: usize) -> &[usize] {
+        &self.predecessors[node]
+    }
+
+    // Kahn's algorithm. Nodes with no ordering constraint between them come
+    // out in ascending order of index.
+    pub fn topological_order(&self) -> Option<Vec<usize>> {
+        let mut in_degree: Vec<_> = self.predecessors.iter().map(|p| p.len()).collect();
+        let mut ready: VecDeque<_> = (0..self.len()).filter(|n| in_degree[*n] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+
+        while let Some(node) = ready.pop_front() {
+            order.push(node);
+
+            for next in &self.successors[node] {
+                in_degree[*next] -= 1;
+                if in_degree[*next] == 0 {
+                    ready.push_back(*next);
+                }
+            }
+        }
+
+        if order.len() == self.len() {
+            Some(order)
+        } else {
+            None
+        }
+    }
+
+    // Dominator tree for paths starting at root. A node a dominates b if every
+    // path from root to b passes through a. Panics if the graph has a cycle.
+    pub fn dominators(&self, root: usize) -> DominatorTree {
+        let order = self
+            .topological_order()
+            .unwrap_or_else(|| panic!("Dominators require an acyclic graph"));
+
+        let mut idom: Vec<Option<usize>> = vec![None; self.len()];
+        let mut depth = vec![0; self.len()];
+        let mut reachable = vec![false; self.len()];
+        reachable[root] = true;
+
+        let mut visited = Vec::new();
+
+        // In topological order every predecessor has its dominator before
+        // the node itself. The immediate dominator is the closest common
+        // ancestor of all reachable predecessors.
+        for node in order {
+            if node == root {
+                visited.push(node);
+                continue;
+            }
+
+            let mut preds = self.predecessors[node].iter().filter(|p| reachable[**p]);
+            let Some(first) = preds.next() else {
+                continue;
+            };
+
+            let dom = preds.fold(*first, |a, b| common_ancestor(&idom, &depth, a, *b));
+
+            idom[node] = Some(dom);
+            depth[node] = depth[dom] + 1;
+            reachable[node] = true;
+            visited.push(node);
+        }
+
+        // Accumulate subtree sizes from the leaves up.
+        let mut size = vec![0; self.len()];
+        for node in visited.iter().rev() {
+            size[*node] += 1;
+            if let Some(parent) = idom[*node] {
+                size[parent] += size[*node];
+            }
+        }
+
+        DominatorTree {
+            root,
+            idom,
+            depth,
+            size,
+        }
+    }
+}
+
+fn common_ancestor(idom: &[Option<usize>], depth: &[usize], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        if depth[a] >= depth[b] {
+            a = idom[a].unwrap();
+        } else {
+            b = idom[b].unwrap();
+        }
+    }
+    a
+}
+
+#[derive(Debug)]
+pub struct DominatorTree {
+    root: usize,
+    idom: Vec<Option<usize>>,
+    depth: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DominatorTree {
+    pub fn root(&self) -> usize {
+        self.root
+    }
+
+    // None for the root and for nodes that cannot be reached from it.
+    pub fn immediate_dominator(&self, node: usize) -> Option<usize> {
+        self.idom[node]
+    }
+
+    pub fn is_reachable(&self, node: usize) -> bool {
+        node == self.root || self.idom[node].is_some()
+    }
+
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        if !self.is_reachable(a) || !self.is_reachable(b) || self.depth[a] > self.depth[b] {
+            return false;
+        }
+
+        let mut current = b;
+        while self.depth[current] > self.depth[a] {
+            current = self.idom[current].unwrap();
+        }
+        current == a
+    }
+
+    // Number of other nodes that can only be reached through this node. If
+    // the node were removed, these are the nodes that would be cut off.
+    pub fn dominated_count(&self, node: usize) -> usize {
+        self.size[node].saturating_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 -> 1 -> 3 -> 4
+    // 0 -> 2 -> 3
+    // 4 -> 5
+    fn create_test_dag() -> Dag {
+        let mut dag = Dag::new(6);
+        dag.add_edge(0, 1);
+        dag.add_edge(0, 2);
+        dag.add_edge(1, 3);
+        dag.add_edge(2, 3);
+        dag.add_edge(3, 4);
+        dag.add_edge(4, 5);
+        dag
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let dag = create_test_dag();
+        assert_eq!(dag.topological_order(), Some(vec![0, 1, 2, 3, 4, 5]));
+    }
+
+    #[test]
+    fn test_topological_order_cycle() {
+        let mut dag = create_test_dag();
+        dag.add_edge(5, 1);
+        assert_eq!(dag.topological_order(), None);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let dag = create_test_dag();
+        assert_eq!(dag.successors(0), &[1, 2]);
+        assert_eq!(dag.predecessors(3), &[1, 2]);
+    }
+
+    #[test]
+    fn test_dominators() {
+        let dag = create_test_dag();
+        let tree = dag.dominators(0);
+
+        assert_eq!(tree.immediate_dominator(0), None);
+        assert_eq!(tree.immediate_dominator(1), Some(0));
+        assert_eq!(tree.immediate_dominator(3), Some(0));
+        assert_eq!(tree.immediate_dominator(5), Some(4));
+
+        assert!(tree.dominates(3, 5));
+        assert!(tree.dominates(0, 5));
+        assert!(!tree.dominates(1, 3));
+        assert!(!tree.dominates(5, 3));
+    }
+
+    #[test]
+    fn test_dominated_count() {
+        let dag = create_test_dag();
+        let tree = dag.dominators(0);
+
+        let counts: Vec<_> = (0..6).map(|n| tree.dominated_count(n)).collect();
+        assert_eq!(counts, vec![5, 0, 0, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_dominators_unreachable() {
+        let mut dag = create_test_dag();
+        let tree = dag.dominators(1);
+
+        assert!(!tree.is_reachable(0));
+        assert!(!tree.is_reachable(2));
+        assert_eq!(tree.immediate_dominator(3), Some(1));
+        assert_eq!(tree.dominated_count(1), 3);
+
+        // Extra edge from an unreachable node does not change anything.
+        dag.add_edge(2, 5);
+        let tree = dag.dominators(1);
+        assert_eq!(tree.immediate_dominator(5), Some(4));
+    }
+}
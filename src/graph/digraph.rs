@@ -0,0 +1,300 @@
+use std::{collections::HashMap, hash::Hash};
+
+// Directed graph with labelled nodes. Nodes are referred to by index, in the
+// order they were first added.
+#[derive(Debug, Clone)]
+pub struct DiGraph<T> {
+    labels: Vec<T>,
+    index: HashMap<T, usize>,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl<T> Default for DiGraph<T> {
+    fn default() -> Self {
+        Self {
+            labels: Vec::new(),
+            index: HashMap::new(),
+            successors: Vec::new(),
+            predecessors: Vec::new(),
+        }
+    }
+}
+
+impl<T> DiGraph<T>
+where
+    T: Hash + Eq + Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    // Returns the index of the node, adding it if it does not exist.
+    pub fn add_node(&mut self, label: T) -> usize {
+        if let Some(i) = self.index.get(&label) {
+            return *i;
+        }
+
+        let i = self.labels.len();
+        self.index.insert(label.clone(), i);
+        self.labels.push(label);
+        self.successors.push(Vec::new());
+        self.predecessors.push(Vec::new());
+        i
+    }
+
+    pub fn add_edge(&mut self, from: T, to: T) {
+        let a = self.add_node(from);
+        let b = self.add_node(to);
+        self.successors[a].push(b);
+        self.predecessors[b].push(a);
+    }
+
+    pub fn index_of(&self, label: &T) -> Option<usize> {
+        self.index.get(label).copied()
+    }
+
+    pub fn label(&self, node: usize) -> &T {
+        &self.labels[node]
+    }
+
+    pub fn successors(&self, node: usize) -> &[usize] {
+        &self.successors[node]
+    }
+
+    pub fn predecessors(&self, node: usize) -> &[usize] {
+        &self.predecessors[node]
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.successors
+            .iter()
+            .enumerate()
+            .flat_map(|(a, s)| s.iter().map(move |b| (a, *b)))
+    }
+
+    // Every node that can be reached from the start node, including itself.
+    pub fn descendants(&self, node: usize) -> Vec<usize> {
+        self.walk(node, &self.successors)
+    }
+
+    // Every node that can reach the given node, including itself.
+    pub fn ancestors(&self, node: usize) -> Vec<usize> {
+        self.walk(node, &self.predecessors)
+    }
+
+    fn walk(&self, start: usize, links: &[Vec<usize>]) -> Vec<usize> {
+        let mut seen = vec![false; self.len()];
+        let mut to_visit = vec![start];
+        seen[start] = true;
+
+        while let Some(current) = to_visit.pop() {
+            for next in &links[current] {
+                if !seen[*next] {
+                    seen[*next] = true;
+                    to_visit.push(*next);
+                }
+            }
+        }
+
+        (0..self.len()).filter(|i| seen[*i]).collect()
+    }
+
+    // Graph containing only the given nodes and the edges between them.
+    pub fn subgraph(&self, nodes: &[usize]) -> DiGraph<T> {
+        let mut result = DiGraph::new();
+        let mut keep = vec![false; self.len()];
+
+        for node in nodes {
+            keep[*node] = true;
+            result.add_node(self.labels[*node].clone());
+        }
+
+        for (a, b) in self.edges().filter(|(a, b)| keep[*a] && keep[*b]) {
+            result.add_edge(self.labels[a].clone(), self.labels[b].clone());
+        }
+
+        result
+    }
+
+    // Tarjan's algorithm, using an explicit stack to avoid recursion limits
+    // on large graphs. Components are returned in reverse topological order,
+    // so a component comes before any component with an edge into it.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        let n = self.len();
+        let mut index = vec![usize::MAX; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        for start in 0..n {
+            if index[start] != usize::MAX {
+                continue;
+            }
+
+            // Each frame is a node and the position of the next successor to
+            // look at.
+            let mut frames = vec![(start, 0)];
+            index[start] = next_index;
+            low[start] = next_index;
+            next_index += 1;
+            stack.push(start);
+            on_stack[start] = true;
+
+            while let Some((node, pos)) = frames.pop() {
+                if let Some(next) = self.successors[node].get(pos) {
+                    frames.push((node, pos + 1));
+
+                    if index[*next] == usize::MAX {
+                        index[*next] = next_index;
+                        low[*next] = next_index;
+                        next_index += 1;
+                        stack.push(*next);
+                        on_stack[*next] = true;
+                        frames.push((*next, 0));
+                    } else if on_stack[*next] {
+                        low[node] = low[node].min(index[*next]);
+                    }
+                    continue;
+                }
+
+                // Finished with this node, pass the low link to the parent.
+                if let Some((parent, _)) = frames.last() {
+                    low[*parent] = low[*parent].min(low[node]);
+                }
+
+                if low[node] == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a -> b -> c -> a, c -> d -> e -> d, f
+    fn create_test_graph() -> DiGraph<&'static str> {
+        let mut graph = DiGraph::new();
+        graph.add_edge("a", "b");
+        graph.add_edge("b", "c");
+        graph.add_edge("c", "a");
+        graph.add_edge("c", "d");
+        graph.add_edge("d", "e");
+        graph.add_edge("e", "d");
+        graph.add_node("f");
+        graph
+    }
+
+    fn labels(graph: &DiGraph<&'static str>, nodes: &[usize]) -> Vec<&'static str> {
+        let mut result: Vec<_> = nodes.iter().map(|n| *graph.label(*n)).collect();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn test_add_node() {
+        let mut graph = create_test_graph();
+        assert_eq!(graph.len(), 6);
+        assert_eq!(graph.add_node("a"), 0);
+        assert_eq!(graph.len(), 6);
+        assert_eq!(graph.index_of(&"d"), Some(3));
+        assert_eq!(graph.index_of(&"z"), None);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let graph = create_test_graph();
+        let c = graph.index_of(&"c").unwrap();
+        let d = graph.index_of(&"d").unwrap();
+
+        assert_eq!(labels(&graph, graph.successors(c)), vec!["a", "d"]);
+        assert_eq!(labels(&graph, graph.predecessors(d)), vec!["c", "e"]);
+    }
+
+    #[test]
+    fn test_descendants_ancestors() {
+        let graph = create_test_graph();
+        let b = graph.index_of(&"b").unwrap();
+        let e = graph.index_of(&"e").unwrap();
+
+        assert_eq!(
+            labels(&graph, &graph.descendants(b)),
+            vec!["a", "b", "c", "d", "e"]
+        );
+        assert_eq!(labels(&graph, &graph.descendants(e)), vec!["d", "e"]);
+        assert_eq!(
+            labels(&graph, &graph.ancestors(e)),
+            vec!["a", "b", "c", "d", "e"]
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let graph = create_test_graph();
+        let components: Vec<_> = graph
+            .strongly_connected_components()
+            .iter()
+            .map(|c| labels(&graph, c))
+            .collect();
+
+        assert_eq!(
+            components,
+            vec![vec!["d", "e"], vec!["a", "b", "c"], vec!["f"]]
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components_long_chain() {
+        let mut graph = DiGraph::new();
+        for i in 0..100000 {
+            graph.add_edge(i, i + 1);
+        }
+        graph.add_edge(100000, 0);
+
+        let components = graph.strongly_connected_components();
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), 100001);
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let graph = create_test_graph();
+        let nodes: Vec<_> = ["c", "d", "e"]
+            .iter()
+            .map(|l| graph.index_of(l).unwrap())
+            .collect();
+
+        let sub = graph.subgraph(&nodes);
+        assert_eq!(sub.len(), 3);
+
+        let edges: Vec<_> = sub
+            .edges()
+            .map(|(a, b)| (*sub.label(a), *sub.label(b)))
+            .collect();
+        assert_eq!(edges, vec![("c", "d"), ("d", "e"), ("e", "d")]);
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write as _},
+    fs::File,
+    hash::Hash,
+    io::{BufWriter, Write},
+};
+
+use super::digraph::DiGraph;
+
+type Attributes = Vec<(String, String)>;
+
+// Builder for Graphviz DOT files. Nodes and edges are written in the order
+// they were added, with every id and attribute value quoted.
+#[derive(Debug, Clone)]
+pub struct Dot {
+    name: String,
+    directed: bool,
+    index: HashMap<String, usize>,
+    nodes: Vec<(String, Attributes)>,
+    edges: Vec<(String, String, Attributes)>,
+}
+
+impl Dot {
+    pub fn digraph(name: &str) -> Self {
+        Self::new(name, true)
+    }
+
+    pub fn graph(name: &str) -> Self {
+        Self::new(name, false)
+    }
+
+    fn new(name: &str, directed: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            directed,
+            index: HashMap::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    // Every node and edge of the graph, using the labels as node ids.
+    pub fn from_digraph<T>(name: &str, graph: &DiGraph<T>) -> Self
+    where
+        T: Hash + Eq + Clone + Display,
+    {
+        let mut dot = Self::digraph(name);
+        for n in 0..graph.len() {
+            dot.node(graph.label(n), &[]);
+        }
+        for (a, b) in graph.edges() {
+            dot.edge(graph.label(a), graph.label(b), &[]);
+        }
+        dot
+    }
+
+    // Adding a node that already exists adds the attributes to it.
+    pub fn node<T: Display>(&mut self, id: T, attributes: &[(&str, &str)]) {
+        let id = id.to_string();
+        let attributes = attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()));
+
+        match self.index.get(&id) {
+            Some(i) => self.nodes[*i].1.extend(attributes),
+            None => {
+                self.index.insert(id.clone(), self.nodes.len());
+                self.nodes.push((id, attributes.collect()));
+            }
+        }
+    }
+
+    pub fn edge<A: Display, B: Display>(&mut self, from: A, to: B, attributes: &[(&str, &str)]) {
+        let attributes = attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        self.edges
+            .push((from.to_string(), to.to_string(), attributes));
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write!(writer, "{}", self)
+    }
+
+    pub fn write_file(&self, path: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+}
+
+impl Display for Dot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (kind, arrow) = if self.directed {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+
+        writeln!(f, "{} {} {{", kind, quote(&self.name))?;
+        for (id, attributes) in self.nodes.iter() {
+            writeln!(f, "    {}{};", quote(id), format_attributes(attributes))?;
+        }
+        for (from, to, attributes) in self.edges.iter() {
+            writeln!(
+                f,
+                "    {} {} {}{};",
+                quote(from),
+                arrow,
+                quote(to),
+                format_attributes(attributes)
+            )?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+fn quote(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn format_attributes(attributes: &[(String, String)]) -> String {
+    if attributes.is_empty() {
+        return String::new();
+    }
+
+    let mut result = String::from(" [");
+    for (i, (k, v)) in attributes.iter().enumerate() {
+        if i > 0 {
+            result.push_str(", ");
+        }
+        write!(result, "{}={}", k, quote(v)).unwrap();
+    }
+    result.push(']');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digraph() {
+        let mut dot = Dot::digraph("test");
+        dot.node("a", &[("shape", "box")]);
+        dot.edge("a", "b", &[("label", "x<10")]);
+        dot.edge("b", "a", &[]);
+
+        assert_eq!(
+            dot.to_string(),
+            "digraph \"test\" {\n    \"a\" [shape=\"box\"];\n    \"a\" -> \"b\" [label=\"x<10\"];\n    \"b\" -> \"a\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_graph() {
+        let mut dot = Dot::graph("g");
+        dot.edge(1, 2, &[("color", "red"), ("penwidth", "3")]);
+
+        assert_eq!(
+            dot.to_string(),
+            "graph \"g\" {\n    \"1\" -- \"2\" [color=\"red\", penwidth=\"3\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_node_attributes_merged() {
+        let mut dot = Dot::digraph("g");
+        dot.node("a", &[("shape", "box")]);
+        dot.node("a", &[("color", "red")]);
+
+        assert_eq!(
+            dot.to_string(),
+            "digraph \"g\" {\n    \"a\" [shape=\"box\", color=\"red\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_quoting() {
+        let mut dot = Dot::digraph("say \"hi\"");
+        dot.node("a\\b", &[]);
+
+        assert_eq!(
+            dot.to_string(),
+            "digraph \"say \\\"hi\\\"\" {\n    \"a\\\\b\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_from_digraph() {
+        let mut graph = DiGraph::new();
+        graph.add_edge("a", "b");
+        graph.add_node("c");
+
+        assert_eq!(
+            Dot::from_digraph("g", &graph).to_string(),
+            "digraph \"g\" {\n    \"a\";\n    \"b\";\n    \"c\";\n    \"a\" -> \"b\";\n}\n"
+        );
+    }
+}
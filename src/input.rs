@@ -1,9 +1,14 @@
 use log::{debug, info};
-use std::fs;
+use std::{collections::HashMap, fmt::Display, fs, str::FromStr, sync::OnceLock};
+
+// Day specific options passed as `--name value`, available to solvers
+// through `option`.
+static OPTIONS: OnceLock<HashMap<String, String>> = OnceLock::new();
 
 pub struct Args {
     pub input_path: std::path::PathBuf,
     pub part_two: bool,
+    pub options: HashMap<String, String>,
 }
 
 impl Args {
@@ -11,15 +16,54 @@ impl Args {
         let day = std::env::args().next().unwrap_or_default();
 
         let mut pargs = pico_args::Arguments::from_env();
+        let part_two = pargs.contains("-2");
+
+        let mut input_path = None;
+        let mut options = HashMap::new();
+        let mut rest = pargs.finish().into_iter();
+
+        while let Some(arg) = rest.next() {
+            let arg = arg
+                .into_string()
+                .map_err(|_| pico_args::Error::NonUtf8Argument)?;
+
+            let Some(name) = arg.strip_prefix("--") else {
+                if input_path.is_some() {
+                    return Err(pico_args::Error::ArgumentParsingFailed {
+                        cause: format!("unexpected argument {}", arg),
+                    });
+                }
+                input_path = Some(arg.into());
+                continue;
+            };
+
+            let (name, value) = match name.split_once('=') {
+                Some((n, v)) => (n.to_string(), v.to_string()),
+                None => {
+                    let value =
+                        rest.next()
+                            .and_then(|v| v.into_string().ok())
+                            .ok_or_else(|| pico_args::Error::ArgumentParsingFailed {
+                                cause: format!("option --{} requires a value", name),
+                            })?;
+                    (name.to_string(), value)
+                }
+            };
+            options.insert(name, value);
+        }
 
         let args = Self {
-            part_two: pargs.contains("-2"),
-            input_path: pargs.free_from_str()?,
+            part_two,
+            input_path: input_path.ok_or(pico_args::Error::MissingArgument)?,
+            options,
         };
 
         let part_num = if args.part_two { 2 } else { 1 };
         info!("Running {}, part {}", day, part_num);
         info!("Input path is {}", args.input_path.display());
+        if !args.options.is_empty() {
+            info!("Options are {:?}", args.options);
+        }
 
         Ok(args)
     }
@@ -32,4 +76,31 @@ impl Args {
 
         return contents.split('\n').map(|s| s.to_string()).collect();
     }
+
+    // Make the options visible to `option`. Only the first call has any
+    // effect.
+    pub fn publish_options(&self) {
+        let _ = OPTIONS.set(self.options.clone());
+    }
+}
+
+// Value of a `--name value` option from the command line, or None if it was
+// not given. Panics if the value cannot be parsed.
+pub fn option<T>(name: &str) -> Option<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = OPTIONS.get()?.get(name)?;
+    let parsed = value
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid value {} for option --{}: {}", value, name, e));
+    Some(parsed)
+}
+
+// Read another input file in the same format as the puzzle input.
+pub fn read_lines(path: &str) -> Vec<String> {
+    let contents =
+        fs::read_to_string(path).unwrap_or_else(|_| panic!("Unable to open file: {}", path));
+    contents.lines().map(|s| s.to_string()).collect()
 }
@@ -1,4 +1,10 @@
+pub mod camel_cards;
 pub mod collections;
+pub mod graph;
 pub mod input;
+pub mod linalg;
+pub mod memo;
+pub mod num_theory;
+pub mod sequence;
 pub mod solver;
 pub mod util;
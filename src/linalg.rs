@@ -0,0 +1,228 @@
+use std::fmt::Display;
+
+use num::{BigRational, Zero};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SolveError {
+    // Matrix rows and rhs do not describe the same number of equations, or
+    // rows have differing lengths.
+    DimensionMismatch,
+
+    // An equation reduced to 0 = c for some non-zero c.
+    Inconsistent { row: usize },
+
+    // The system is consistent but has fewer independent equations than
+    // unknowns, so there is no unique solution.
+    Singular { rank: usize, unknowns: usize },
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::DimensionMismatch => write!(f, "Matrix dimensions do not match"),
+            SolveError::Inconsistent { row } => {
+                write!(
+                    f,
+                    "System is inconsistent (equation {} reduces to 0 = c)",
+                    row
+                )
+            }
+            SolveError::Singular { rank, unknowns } => write!(
+                f,
+                "System is singular (rank {} for {} unknowns)",
+                rank, unknowns
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+// Reduced row echelon form of an augmented matrix. Each row holds the
+// coefficients followed by the right hand side. Rows are reordered while
+// reducing, so the original index of each row is tracked alongside it.
+struct Echelon {
+    rows: Vec<Vec<BigRational>>,
+    order: Vec<usize>,
+    pivots: Vec<usize>,
+}
+
+impl Echelon {
+    fn reduce(mut rows: Vec<Vec<BigRational>>, unknowns: usize) -> Self {
+        let mut order: Vec<_> = (0..rows.len()).collect();
+        let mut pivots = Vec::new();
+
+        for col in 0..unknowns {
+            let r = pivots.len();
+            let Some(pivot) = (r..rows.len()).find(|&i| !rows[i][col].is_zero()) else {
+                continue;
+            };
+            rows.swap(r, pivot);
+            order.swap(r, pivot);
+
+            let scale = rows[r][col].clone();
+            rows[r].iter_mut().for_each(|v| *v /= &scale);
+
+            for i in 0..rows.len() {
+                if i == r || rows[i][col].is_zero() {
+                    continue;
+                }
+
+                let factor = rows[i][col].clone();
+                for j in col..rows[i].len() {
+                    let delta = &factor * &rows[r][j];
+                    rows[i][j] -= delta;
+                }
+            }
+
+            pivots.push(col);
+        }
+
+        Self {
+            rows,
+            order,
+            pivots,
+        }
+    }
+
+    fn rank(&self) -> usize {
+        self.pivots.len()
+    }
+}
+
+pub fn rank(matrix: &[Vec<BigRational>]) -> usize {
+    if matrix.is_empty() {
+        return 0;
+    }
+
+    let unknowns = matrix[0].len();
+    Echelon::reduce(matrix.to_vec(), unknowns).rank()
+}
+
+pub fn solve(
+    matrix: &[Vec<BigRational>],
+    rhs: &[BigRational],
+) -> Result<Vec<BigRational>, SolveError> {
+    if matrix.len() != rhs.len() {
+        return Err(SolveError::DimensionMismatch);
+    }
+
+    let unknowns = matrix.first().map_or(0, |r| r.len());
+    if matrix.iter().any(|r| r.len() != unknowns) {
+        return Err(SolveError::DimensionMismatch);
+    }
+
+    let augmented: Vec<_> = matrix
+        .iter()
+        .zip(rhs)
+        .map(|(row, b)| row.iter().chain(std::iter::once(b)).cloned().collect())
+        .collect();
+
+    let echelon = Echelon::reduce(augmented, unknowns);
+    let rank = echelon.rank();
+
+    // Any row below the pivots has all zero coefficients, so the rhs must
+    // also be zero.
+    if let Some(i) = (rank..echelon.rows.len()).find(|&i| !echelon.rows[i][unknowns].is_zero()) {
+        return Err(SolveError::Inconsistent {
+            row: echelon.order[i],
+        });
+    }
+
+    if rank < unknowns {
+        return Err(SolveError::Singular { rank, unknowns });
+    }
+
+    Ok(echelon
+        .rows
+        .into_iter()
+        .take(unknowns)
+        .map(|mut row| row.swap_remove(unknowns))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_rational(values: &[i64]) -> Vec<BigRational> {
+        values
+            .iter()
+            .map(|v| BigRational::from_integer((*v).into()))
+            .collect()
+    }
+
+    fn to_matrix(rows: &[&[i64]]) -> Vec<Vec<BigRational>> {
+        rows.iter().map(|r| to_rational(r)).collect()
+    }
+
+    #[test]
+    fn test_solve() {
+        // x + y + z = 6, 2y + 5z = -4, 2x + 5y - z = 27
+        let matrix = to_matrix(&[&[1, 1, 1], &[0, 2, 5], &[2, 5, -1]]);
+        let rhs = to_rational(&[6, -4, 27]);
+
+        let result = solve(&matrix, &rhs).unwrap();
+        assert_eq!(result, to_rational(&[5, 3, -2]));
+    }
+
+    #[test]
+    fn test_solve_fractional() {
+        // 2x = 1, 3y = 2
+        let matrix = to_matrix(&[&[2, 0], &[0, 3]]);
+        let rhs = to_rational(&[1, 2]);
+
+        let result = solve(&matrix, &rhs).unwrap();
+        assert_eq!(result[0], BigRational::new(1.into(), 2.into()));
+        assert_eq!(result[1], BigRational::new(2.into(), 3.into()));
+    }
+
+    #[test]
+    fn test_solve_overdetermined() {
+        // Third equation is the sum of the first two.
+        let matrix = to_matrix(&[&[1, 2], &[3, -1], &[4, 1]]);
+        let rhs = to_rational(&[5, 1, 6]);
+
+        let result = solve(&matrix, &rhs).unwrap();
+        assert_eq!(result, to_rational(&[1, 2]));
+    }
+
+    #[test]
+    fn test_solve_singular() {
+        let matrix = to_matrix(&[&[1, 2], &[2, 4]]);
+        let rhs = to_rational(&[3, 6]);
+
+        let result = solve(&matrix, &rhs);
+        assert_eq!(
+            result,
+            Err(SolveError::Singular {
+                rank: 1,
+                unknowns: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_solve_inconsistent() {
+        let matrix = to_matrix(&[&[1, 2], &[2, 4]]);
+        let rhs = to_rational(&[3, 7]);
+
+        let result = solve(&matrix, &rhs);
+        assert_eq!(result, Err(SolveError::Inconsistent { row: 1 }));
+    }
+
+    #[test]
+    fn test_solve_dimension_mismatch() {
+        let matrix = to_matrix(&[&[1, 2], &[3, 4]]);
+        let rhs = to_rational(&[1]);
+
+        assert_eq!(solve(&matrix, &rhs), Err(SolveError::DimensionMismatch));
+    }
+
+    #[test]
+    fn test_rank() {
+        assert_eq!(rank(&to_matrix(&[&[1, 2], &[2, 4]])), 1);
+        assert_eq!(rank(&to_matrix(&[&[1, 2], &[3, 4]])), 2);
+        assert_eq!(rank(&to_matrix(&[&[0, 0], &[0, 0]])), 0);
+    }
+}
@@ -0,0 +1,172 @@
+use std::{collections::HashMap, hash::Hash};
+
+// Cache of results for a recursive function. Results are only stored while
+// the cache is below its limit, after which new results are computed but not
+// kept.
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    limit: Option<usize>,
+    hits: usize,
+    misses: usize,
+}
+
+// Handle passed to the memoized function, used to make recursive calls that
+// go through the cache.
+pub struct MemoContext<'a, K, V> {
+    memo: &'a mut Memo<K, V>,
+    f: &'a dyn Fn(&mut MemoContext<K, V>, &K) -> V,
+}
+
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self {
+            cache: HashMap::new(),
+            limit: None,
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+
+impl<K, V> Memo<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Self::default()
+        }
+    }
+
+    pub fn compute<F>(&mut self, key: K, f: F) -> V
+    where
+        F: Fn(&mut MemoContext<K, V>, &K) -> V,
+    {
+        let mut ctx = MemoContext { memo: self, f: &f };
+        ctx.call(key)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.cache.get(key)
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        match self.limit {
+            Some(limit) if self.cache.len() >= limit => (),
+            _ => {
+                self.cache.insert(key, value);
+            }
+        }
+    }
+}
+
+impl<'a, K, V> MemoContext<'a, K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn call(&mut self, key: K) -> V {
+        if let Some(cached) = self.memo.cache.get(&key) {
+            self.memo.hits += 1;
+            return cached.clone();
+        }
+
+        self.memo.misses += 1;
+
+        let f = self.f;
+        let value = f(self, &key);
+        self.memo.insert(key, value.clone());
+        value
+    }
+}
+
+pub fn memoize<K, V, F>(key: K, f: F) -> V
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    F: Fn(&mut MemoContext<K, V>, &K) -> V,
+{
+    Memo::new().compute(key, f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fibonacci(ctx: &mut MemoContext<u64, u64>, n: &u64) -> u64 {
+        match n {
+            0 | 1 => *n,
+            _ => ctx.call(n - 1) + ctx.call(n - 2),
+        }
+    }
+
+    #[test]
+    fn test_memoize() {
+        assert_eq!(memoize(90, fibonacci), 2880067194370816120);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut memo = Memo::new();
+        assert_eq!(memo.compute(10, fibonacci), 55);
+
+        // Every value from 0 to 10 is computed once, and fib(n - 2) is a hit
+        // for every n from 2 to 10, except fib(0) which is computed as the
+        // second call of fib(2).
+        assert_eq!(memo.misses(), 11);
+        assert_eq!(memo.hits(), 8);
+        assert_eq!(memo.len(), 11);
+
+        // Repeated call is served from the cache.
+        assert_eq!(memo.compute(10, fibonacci), 55);
+        assert_eq!(memo.hits(), 9);
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut memo = Memo::with_limit(5);
+        assert_eq!(memo.compute(20, fibonacci), 6765);
+        assert_eq!(memo.len(), 5);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut memo = Memo::new();
+        memo.compute(10, fibonacci);
+        memo.clear();
+
+        assert!(memo.is_empty());
+        assert_eq!(memo.hits(), 0);
+        assert_eq!(memo.misses(), 0);
+        assert!(memo.get(&10).is_none());
+    }
+}
@@ -0,0 +1,349 @@
+use std::{collections::HashMap, hash::Hash};
+
+use num::{integer::Roots, Integer};
+
+use crate::util::product;
+
+// Returns (g, x, y) such that a * x + b * y = g = gcd(a, b).
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        if a < 0 {
+            return (-a, -1, 0);
+        }
+        return (a, 1, 0);
+    }
+
+    let (g, x, y) = extended_gcd(b, a.rem_euclid(b));
+    (g, y, x - a.div_euclid(b) * y)
+}
+
+// Combine congruences of the form x = residue (mod modulus) into a single
+// congruence. Moduli do not need to be coprime. Returns None if there is no
+// value satisfying every congruence.
+pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    let mut result = (0, 1);
+
+    for (residue, modulus) in congruences {
+        let (r1, m1) = result;
+        let (r2, m2) = (residue.rem_euclid(*modulus), *modulus);
+
+        let (g, p, _) = extended_gcd(m1, m2);
+        if (r2 - r1) % g != 0 {
+            return None;
+        }
+
+        let lcm = m1 / g * m2;
+        let step = ((r2 - r1) / g).rem_euclid(m2 / g) * p.rem_euclid(m2 / g) % (m2 / g);
+        result = ((r1 + m1 * step).rem_euclid(lcm), lcm);
+    }
+
+    Some(result)
+}
+
+// Sequence of states that eventually repeats. States from `start` onwards
+// repeat every `length` steps. `hits` holds every step, before the sequence
+// first returns to a previous state, where the state was of interest.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+    pub hits: Vec<usize>,
+}
+
+impl Cycle {
+    pub fn find<S, F, P>(initial: S, mut step: F, is_hit: P) -> Self
+    where
+        S: Hash + Eq + Clone,
+        F: FnMut(&S) -> S,
+        P: Fn(&S) -> bool,
+    {
+        let mut seen: HashMap<S, usize> = HashMap::new();
+        let mut hits = Vec::new();
+        let mut current = initial;
+
+        for t in 0.. {
+            if let Some(start) = seen.get(&current) {
+                return Self {
+                    start: *start,
+                    length: t - start,
+                    hits,
+                };
+            }
+
+            if is_hit(&current) {
+                hits.push(t);
+            }
+
+            let next = step(&current);
+            seen.insert(current, t);
+            current = next;
+        }
+
+        unreachable!()
+    }
+
+    pub fn is_hit(&self, t: usize) -> bool {
+        let t = if t < self.start {
+            t
+        } else {
+            self.start + (t - self.start) % self.length
+        };
+
+        self.hits.binary_search(&t).is_ok()
+    }
+}
+
+// Smallest step, no less than `min`, where every cycle has a hit.
+pub fn first_common_hit(cycles: &[Cycle], min: usize) -> Option<usize> {
+    // Before every cycle has started, check each step directly.
+    let settled = cycles.iter().map(|c| c.start).max().unwrap_or(0).max(min);
+    if let Some(t) = (min..settled).find(|t| cycles.iter().all(|c| c.is_hit(*t))) {
+        return Some(t);
+    }
+
+    // After that each cycle hits on a fixed set of residues. Try every
+    // combination of residues and keep the earliest solution.
+    let residues: Vec<Vec<(i128, i128)>> = cycles
+        .iter()
+        .map(|c| {
+            c.hits
+                .iter()
+                .filter(|h| **h >= c.start)
+                .map(|h| (*h as i128, c.length as i128))
+                .collect()
+        })
+        .collect();
+    let slices: Vec<_> = residues.iter().map(|r| &r[..]).collect();
+
+    product(&slices)
+        .filter_map(|choice| {
+            let congruences: Vec<_> = choice.into_iter().copied().collect();
+            crt(&congruences)
+        })
+        .map(|(r, m)| {
+            let settled = settled as i128;
+            let t = if r >= settled {
+                r
+            } else {
+                r + (settled - r + m - 1) / m * m
+            };
+            t as usize
+        })
+        .min()
+}
+
+// Number of ways to split total into x + y, with x and y non-negative
+// integers, such that x * y > threshold. The boundary comes from the roots of
+// x^2 - total * x + threshold, using an integer square root so the result is
+// exact. total^2 must fit in T, use BigUint for very large values.
+pub fn count_products_exceeding<T>(total: T, threshold: T) -> T
+where
+    T: Integer + Roots + Clone,
+{
+    let two = T::one() + T::one();
+    let wins = |x: &T| x.clone() * (total.clone() - x.clone()) > threshold;
+
+    // The product is largest with the split closest to the middle.
+    let middle = total.clone() / two.clone();
+    if !wins(&middle) {
+        return T::zero();
+    }
+
+    // The middle wins, so total^2 > 4 * threshold.
+    let discriminant =
+        total.clone() * total.clone() - two.clone() * two.clone() * threshold.clone();
+    let root = discriminant.sqrt();
+
+    // The rounded root puts the estimate within one of the smallest winner.
+    let mut low = (total.clone() - root) / two;
+    while !wins(&low) {
+        low = low + T::one();
+    }
+    while low > T::zero() && wins(&(low.clone() - T::one())) {
+        low = low - T::one();
+    }
+
+    let high = total - low.clone();
+    high - low + T::one()
+}
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+
+    use super::*;
+
+    fn brute_force(total: u64, threshold: u64) -> u64 {
+        (0..=total).filter(|x| x * (total - x) > threshold).count() as u64
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let (g, x, y) = extended_gcd(240, 46);
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+
+        assert_eq!(extended_gcd(7, 0), (7, 1, 0));
+        assert_eq!(extended_gcd(0, 5).0, 5);
+    }
+
+    #[test]
+    fn test_crt_coprime() {
+        let result = crt(&[(2, 3), (3, 5), (2, 7)]);
+        assert_eq!(result, Some((23, 105)));
+    }
+
+    #[test]
+    fn test_crt_not_coprime() {
+        // x = 3 mod 4 and x = 5 mod 6 gives x = 11 mod 12
+        assert_eq!(crt(&[(3, 4), (5, 6)]), Some((11, 12)));
+    }
+
+    #[test]
+    fn test_crt_no_solution() {
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+    }
+
+    #[test]
+    fn test_crt_empty() {
+        assert_eq!(crt(&[]), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_cycle_find() {
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 2
+        let cycle = Cycle::find(0, |s| if *s == 4 { 2 } else { s + 1 }, |s| s % 2 == 1);
+
+        assert_eq!(cycle.start, 2);
+        assert_eq!(cycle.length, 3);
+        assert_eq!(cycle.hits, vec![1, 3]);
+
+        assert!(cycle.is_hit(1));
+        assert!(!cycle.is_hit(4));
+        assert!(cycle.is_hit(6));
+        assert!(!cycle.is_hit(7));
+    }
+
+    #[test]
+    fn test_first_common_hit_lcm() {
+        let a = Cycle {
+            start: 0,
+            length: 4,
+            hits: vec![0],
+        };
+        let b = Cycle {
+            start: 0,
+            length: 6,
+            hits: vec![0],
+        };
+
+        assert_eq!(first_common_hit(&[a, b], 1), Some(12));
+    }
+
+    #[test]
+    fn test_first_common_hit_offsets() {
+        // Hits at 3, 8, 13, ... and at 1, 5, 9, 13, ...
+        let a = Cycle {
+            start: 2,
+            length: 5,
+            hits: vec![3],
+        };
+        let b = Cycle {
+            start: 0,
+            length: 4,
+            hits: vec![1],
+        };
+
+        assert_eq!(first_common_hit(&[a, b], 1), Some(13));
+    }
+
+    #[test]
+    fn test_first_common_hit_before_cycle() {
+        let a = Cycle {
+            start: 5,
+            length: 2,
+            hits: vec![2],
+        };
+        let b = Cycle {
+            start: 0,
+            length: 1,
+            hits: vec![0],
+        };
+
+        assert_eq!(first_common_hit(&[a, b], 1), Some(2));
+    }
+
+    #[test]
+    fn test_first_common_hit_none() {
+        let a = Cycle {
+            start: 0,
+            length: 2,
+            hits: vec![0],
+        };
+        let b = Cycle {
+            start: 0,
+            length: 2,
+            hits: vec![1],
+        };
+
+        assert_eq!(first_common_hit(&[a, b], 1), None);
+    }
+
+    #[test]
+    fn test_count_products_exceeding() {
+        assert_eq!(count_products_exceeding(7u64, 9), 4);
+        assert_eq!(count_products_exceeding(15u64, 40), 8);
+        assert_eq!(count_products_exceeding(30u64, 200), 9);
+        assert_eq!(count_products_exceeding(71530u64, 940200), 71503);
+    }
+
+    #[test]
+    fn test_count_products_exceeding_brute_force() {
+        for total in 0..80u64 {
+            for threshold in 0..=(total * total / 4 + 2) {
+                assert_eq!(
+                    count_products_exceeding(total, threshold),
+                    brute_force(total, threshold),
+                    "total {} threshold {}",
+                    total,
+                    threshold
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_products_exceeding_large() {
+        // Exact squares either side of the threshold.
+        let total: u128 = 2_000_000_000_000_000_000;
+        let half = total / 2;
+        assert_eq!(count_products_exceeding(total, half * half), 0);
+        assert_eq!(count_products_exceeding(total, half * half - 1), 1);
+        assert_eq!(count_products_exceeding(total, (half - 1) * (half + 1)), 1);
+        assert_eq!(
+            count_products_exceeding(total, (half - 1) * (half + 1) - 1),
+            3
+        );
+
+        let expected = count_products_exceeding(total, 123_456_789_123_456_789_123);
+        let big = count_products_exceeding(
+            BigUint::from(total),
+            BigUint::from(123_456_789_123_456_789_123u128),
+        );
+        assert_eq!(big, BigUint::from(expected));
+    }
+
+    #[test]
+    fn test_count_products_exceeding_big() {
+        // Far beyond u128, check the result is exactly on the boundary.
+        let total = BigUint::from(10u32).pow(60) + BigUint::from(7u32);
+        let threshold = BigUint::from(10u32).pow(119) * BigUint::from(2u32);
+        let count = count_products_exceeding(total.clone(), threshold.clone());
+
+        let low = (total.clone() + BigUint::from(1u32) - count.clone()) / BigUint::from(2u32);
+        let product = |x: &BigUint| x * (&total - x);
+        assert!(product(&low) > threshold);
+        assert!(product(&(&low - BigUint::from(1u32))) <= threshold);
+        assert_eq!(&total - &low - &low + BigUint::from(1u32), count);
+    }
+}
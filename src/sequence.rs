@@ -0,0 +1,217 @@
+use num::{BigRational, Zero};
+
+// Table of repeated finite differences. The first row holds the original
+// values and each following row the differences of the row above. Building
+// stops once a row is constant, as every row after it would be all zero.
+#[derive(Debug)]
+pub struct DifferenceTable {
+    rows: Vec<Vec<i64>>,
+}
+
+impl DifferenceTable {
+    pub fn new(values: &[i64]) -> Self {
+        if values.is_empty() {
+            panic!("Cannot build difference table from an empty sequence");
+        }
+
+        let mut rows = vec![values.to_vec()];
+
+        while !is_constant(rows.last().unwrap()) {
+            let diff = differences(rows.last().unwrap());
+            rows.push(diff);
+        }
+
+        Self { rows }
+    }
+
+    pub fn rows(&self) -> &[Vec<i64>] {
+        &self.rows
+    }
+
+    // Degree of the lowest order polynomial that fits the values. Only known
+    // if the constant row has at least two entries, otherwise the values may
+    // just be too few to show the pattern.
+    pub fn degree(&self) -> Option<usize> {
+        match self.rows.last() {
+            Some(last) if last.len() > 1 => Some(self.rows.len() - 1),
+            _ => None,
+        }
+    }
+
+    // Newton forward formula, using the leading value of each row. Index 0 is
+    // the first value in the sequence.
+    pub fn forward(&self, index: i64) -> i64 {
+        let n = index as i128;
+        let mut coefficient: i128 = 1;
+        let mut total: i128 = 0;
+
+        for (k, row) in self.rows.iter().enumerate() {
+            if k > 0 {
+                let k = k as i128;
+                coefficient = coefficient * (n - k + 1) / k;
+            }
+            total += coefficient * row[0] as i128;
+        }
+
+        to_i64(total)
+    }
+
+    // Newton backward formula, using the trailing value of each row. Offset 0
+    // is the last value in the sequence.
+    pub fn backward(&self, offset: i64) -> i64 {
+        let t = offset as i128;
+        let mut coefficient: i128 = 1;
+        let mut total: i128 = 0;
+
+        for (k, row) in self.rows.iter().enumerate() {
+            if k > 0 {
+                let k = k as i128;
+                coefficient = coefficient * (t + k - 1) / k;
+            }
+            total += coefficient * *row.last().unwrap() as i128;
+        }
+
+        to_i64(total)
+    }
+
+    pub fn next(&self) -> i64 {
+        self.backward(1)
+    }
+
+    pub fn previous(&self) -> i64 {
+        self.forward(-1)
+    }
+}
+
+fn to_i64(value: i128) -> i64 {
+    value
+        .try_into()
+        .unwrap_or_else(|_| panic!("Extrapolated value {} does not fit in i64", value))
+}
+
+fn is_constant(values: &[i64]) -> bool {
+    values.iter().all(|v| *v == values[0])
+}
+
+pub fn differences(values: &[i64]) -> Vec<i64> {
+    values.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+pub fn minimal_degree(values: &[i64]) -> Option<usize> {
+    DifferenceTable::new(values).degree()
+}
+
+// Value at an arbitrary index, where index 0 is the first value. Indexes past
+// the end are calculated from the trailing differences.
+pub fn extrapolate(values: &[i64], index: i64) -> i64 {
+    let table = DifferenceTable::new(values);
+    let last = values.len() as i64 - 1;
+
+    if index > last {
+        table.backward(index - last)
+    } else {
+        table.forward(index)
+    }
+}
+
+// Value at x of the polynomial passing through every point. The x values must
+// be distinct.
+pub fn lagrange(points: &[(i64, i64)], x: i64) -> BigRational {
+    let mut total = BigRational::zero();
+
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        let mut term = BigRational::from_integer((*yi).into());
+
+        for (j, (xj, _)) in points.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+
+            if xi == xj {
+                panic!("Duplicate x value {} in interpolation points", xi);
+            }
+
+            term *= BigRational::new((x - xj).into(), (xi - xj).into());
+        }
+
+        total += term;
+    }
+
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_differences() {
+        assert_eq!(differences(&[1, 3, 6, 10]), vec![2, 3, 4]);
+        assert!(differences(&[1]).is_empty());
+    }
+
+    #[test]
+    fn test_table_rows() {
+        let table = DifferenceTable::new(&[1, 3, 6, 10, 15, 21]);
+        assert_eq!(table.rows().len(), 3);
+        assert_eq!(table.rows()[1], vec![2, 3, 4, 5, 6]);
+        assert_eq!(table.rows()[2], vec![1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_degree() {
+        assert_eq!(minimal_degree(&[5, 5, 5]), Some(0));
+        assert_eq!(minimal_degree(&[0, 3, 6, 9, 12, 15]), Some(1));
+        assert_eq!(minimal_degree(&[1, 3, 6, 10, 15, 21]), Some(2));
+        assert_eq!(minimal_degree(&[1, 2, 4]), None);
+    }
+
+    #[test]
+    fn test_next() {
+        assert_eq!(DifferenceTable::new(&[0, 3, 6, 9, 12, 15]).next(), 18);
+        assert_eq!(DifferenceTable::new(&[1, 3, 6, 10, 15, 21]).next(), 28);
+        assert_eq!(DifferenceTable::new(&[10, 13, 16, 21, 30, 45]).next(), 68);
+    }
+
+    #[test]
+    fn test_previous() {
+        assert_eq!(DifferenceTable::new(&[0, 3, 6, 9, 12, 15]).previous(), -3);
+        assert_eq!(DifferenceTable::new(&[1, 3, 6, 10, 15, 21]).previous(), 0);
+        assert_eq!(
+            DifferenceTable::new(&[10, 13, 16, 21, 30, 45]).previous(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        // n^2 + 1
+        let values = [1, 2, 5, 10];
+        assert_eq!(extrapolate(&values, 2), 5);
+        assert_eq!(extrapolate(&values, 100), 10001);
+        assert_eq!(extrapolate(&values, -3), 10);
+        assert_eq!(extrapolate(&values, 202300), 202300 * 202300 + 1);
+    }
+
+    #[test]
+    fn test_forward_backward_agree() {
+        let table = DifferenceTable::new(&[2, -1, 4, 23, 62]);
+        for i in -5..20 {
+            assert_eq!(table.forward(i), table.backward(i - 4));
+        }
+    }
+
+    #[test]
+    fn test_lagrange() {
+        // n^2 + 1
+        let points = [(0, 1), (1, 2), (3, 10)];
+        assert_eq!(lagrange(&points, 2), BigRational::from_integer(5.into()));
+        assert_eq!(lagrange(&points, 10), BigRational::from_integer(101.into()));
+    }
+
+    #[test]
+    fn test_lagrange_fractional() {
+        let points = [(0, 0), (2, 1)];
+        assert_eq!(lagrange(&points, 1), BigRational::new(1.into(), 2.into()));
+    }
+}
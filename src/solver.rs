@@ -1,3 +1,22 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+
+static THREADS: OnceLock<usize> = OnceLock::new();
+
+// Set once a day takes its parallel path, so the runner knows to time a
+// sequential run to compare it with.
+static PARALLEL_USED: AtomicBool = AtomicBool::new(false);
+
+// Set during that sequential run.
+static COMPARING: AtomicBool = AtomicBool::new(false);
+
 #[macro_export]
 macro_rules! solver {
     ($p1:ident) => {
@@ -26,12 +45,82 @@ where
         Err(e) => panic!("Failed to parse args {}", e),
     };
 
+    args.publish_options();
+    if let Some(threads) = crate::input::option::<usize>("threads") {
+        configure_threads(threads);
+    }
+
     let lines = args.read_input_file();
 
-    let result = if !args.part_two {
-        part1(&lines)
-    } else {
-        part2(&lines)
+    let solve = || {
+        let start = Instant::now();
+        let result = if !args.part_two {
+            part1(&lines)
+        } else {
+            part2(&lines)
+        };
+        (result, start.elapsed())
     };
+
+    let (result, elapsed) = solve();
+    if PARALLEL_USED.load(Ordering::Relaxed) {
+        COMPARING.store(true, Ordering::Relaxed);
+        let (expected, sequential) = solve();
+        COMPARING.store(false, Ordering::Relaxed);
+
+        log_comparison(sequential, elapsed);
+        if result != expected {
+            warn!("Sequential run gave {} instead", expected);
+        }
+    } else {
+        info!("Solved in {:?} using 1 thread", elapsed);
+    }
+
     println!("{}", result);
 }
+
+fn log_comparison(sequential: Duration, parallel: Duration) {
+    info!("Solved in {:?} using 1 thread", sequential);
+    info!(
+        "Solved in {:?} using {} threads, {:.2}x the sequential speed",
+        parallel,
+        threads(),
+        sequential.as_secs_f64() / parallel.as_secs_f64()
+    );
+}
+
+// Size the global rayon pool. Days only take their parallel paths when more
+// than one thread is configured, so --threads 1 runs everything sequentially.
+fn configure_threads(threads: usize) {
+    if threads == 0 {
+        panic!("Thread count must be at least 1");
+    }
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .unwrap_or_else(|e| panic!("Failed to create thread pool: {}", e));
+    let _ = THREADS.set(threads);
+}
+
+// Number of threads given with --threads, or 1 if it was not set.
+pub fn threads() -> usize {
+    THREADS.get().copied().unwrap_or(1)
+}
+
+pub fn is_parallel() -> bool {
+    let parallel = threads() > 1 && !COMPARING.load(Ordering::Relaxed);
+    if parallel {
+        PARALLEL_USED.store(true, Ordering::Relaxed);
+    }
+    parallel
+}
+
+// Value of a `--name <file>` option for a file the day writes. None during the
+// sequential comparison run, so each file is only written once.
+pub fn output_path(name: &str) -> Option<String> {
+    if COMPARING.load(Ordering::Relaxed) {
+        return None;
+    }
+    crate::input::option(name)
+}
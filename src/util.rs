@@ -1,6 +1,11 @@
-use std::{fmt::Debug, str::FromStr};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    str::FromStr,
+};
 
 use lazy_static::lazy_static;
+use rayon::prelude::*;
 use regex::Regex;
 
 lazy_static! {
@@ -51,6 +56,410 @@ pub fn combinations_3<T>(values: &[T]) -> impl Iterator<Item = (&T, &T, &T)> {
     })
 }
 
+// Step a set of ascending indices to the next combination in lexicographic
+// order. Positions before `fixed` are left alone. Returns false once every
+// combination has been produced.
+fn advance_combination(indices: &mut [usize], n: usize, fixed: usize) -> bool {
+    let k = indices.len();
+
+    let Some(i) = (fixed..k).rev().find(|&i| indices[i] < n - k + i) else {
+        return false;
+    };
+
+    indices[i] += 1;
+    for j in i + 1..k {
+        indices[j] = indices[j - 1] + 1;
+    }
+
+    true
+}
+
+fn first_combination(indices: &mut [usize], first: usize) {
+    indices
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, v)| *v = first + i);
+}
+
+pub struct IndexCombinations<const K: usize> {
+    n: usize,
+    indices: [usize; K],
+    fixed: usize,
+    done: bool,
+}
+
+impl<const K: usize> IndexCombinations<K> {
+    fn new(n: usize, first: usize, fixed: usize) -> Self {
+        let mut indices = [0; K];
+        first_combination(&mut indices, first);
+
+        Self {
+            n,
+            indices,
+            fixed,
+            done: first + K > n,
+        }
+    }
+}
+
+impl<const K: usize> Iterator for IndexCombinations<K> {
+    type Item = [usize; K];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.indices;
+        self.done = !advance_combination(&mut self.indices, self.n, self.fixed);
+        Some(result)
+    }
+}
+
+pub struct Combinations<'a, T> {
+    values: &'a [T],
+    indices: Vec<usize>,
+    fixed: usize,
+    done: bool,
+}
+
+impl<'a, T> Combinations<'a, T> {
+    fn new(values: &'a [T], k: usize, first: usize, fixed: usize) -> Self {
+        let mut indices = vec![0; k];
+        first_combination(&mut indices, first);
+
+        Self {
+            values,
+            indices,
+            fixed,
+            done: first + k > values.len(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Combinations<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.indices.iter().map(|i| &self.values[*i]).collect();
+        self.done = !advance_combination(&mut self.indices, self.values.len(), self.fixed);
+        Some(result)
+    }
+}
+
+// Every combination of K indices taken from 0..n, in lexicographic order.
+pub fn index_combinations<const K: usize>(n: usize) -> IndexCombinations<K> {
+    IndexCombinations::new(n, 0, 0)
+}
+
+// Parallel version of index_combinations. Work is split by the first index of
+// each combination.
+pub fn par_index_combinations<const K: usize>(
+    n: usize,
+) -> impl ParallelIterator<Item = [usize; K]> {
+    if K == 0 {
+        panic!("Parallel combinations require at least one element");
+    }
+
+    (0..n)
+        .into_par_iter()
+        .flat_map_iter(move |first| IndexCombinations::<K>::new(n, first, 1))
+}
+
+pub fn combinations_array<const K: usize, T>(values: &[T]) -> impl Iterator<Item = [&T; K]> {
+    index_combinations::<K>(values.len()).map(move |idx| idx.map(|i| &values[i]))
+}
+
+pub fn combinations_k<T>(values: &[T], k: usize) -> Combinations<'_, T> {
+    Combinations::new(values, k, 0, 0)
+}
+
+pub fn par_combinations_k<T: Sync>(
+    values: &[T],
+    k: usize,
+) -> impl ParallelIterator<Item = Vec<&T>> {
+    if k == 0 {
+        panic!("Parallel combinations require at least one element");
+    }
+
+    (0..values.len())
+        .into_par_iter()
+        .flat_map_iter(move |first| Combinations::new(values, k, first, 1))
+}
+
+// Ordered selections of k indices from 0..n, in lexicographic order. The
+// first k entries of `indices` are the current selection.
+pub struct IndexPermutations {
+    indices: Vec<usize>,
+    k: usize,
+    done: bool,
+}
+
+impl IndexPermutations {
+    fn new(n: usize, k: usize) -> Self {
+        Self {
+            indices: (0..n).collect(),
+            k,
+            done: k > n,
+        }
+    }
+
+    fn advance(&mut self) -> bool {
+        // Reversing the unused tail makes the standard next permutation step
+        // move on to the next distinct prefix.
+        self.indices[self.k..].reverse();
+
+        let n = self.indices.len();
+        let Some(i) = (0..n.saturating_sub(1))
+            .rev()
+            .find(|&i| self.indices[i] < self.indices[i + 1])
+        else {
+            return false;
+        };
+
+        let j = (i + 1..n)
+            .rev()
+            .find(|&j| self.indices[j] > self.indices[i]);
+        self.indices.swap(i, j.unwrap());
+        self.indices[i + 1..].reverse();
+        true
+    }
+}
+
+impl Iterator for IndexPermutations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.indices[..self.k].to_vec();
+        self.done = !self.advance();
+        Some(result)
+    }
+}
+
+pub fn index_permutations(n: usize, k: usize) -> IndexPermutations {
+    IndexPermutations::new(n, k)
+}
+
+pub fn permutations<T>(values: &[T], k: usize) -> impl Iterator<Item = Vec<&T>> {
+    index_permutations(values.len(), k).map(move |idx| idx.iter().map(|i| &values[*i]).collect())
+}
+
+// Cartesian product of several slices, with the last slice changing fastest.
+pub struct Product<'a, T> {
+    slices: Vec<&'a [T]>,
+    indices: Vec<usize>,
+    fixed: usize,
+    done: bool,
+}
+
+impl<'a, T> Product<'a, T> {
+    fn new(slices: &[&'a [T]], first: usize, fixed: usize) -> Self {
+        let mut indices = vec![0; slices.len()];
+        if let Some(v) = indices.first_mut() {
+            *v = first;
+        }
+
+        Self {
+            slices: slices.to_vec(),
+            indices,
+            fixed,
+            done: slices.iter().any(|s| s.is_empty()),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Product<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self
+            .indices
+            .iter()
+            .zip(&self.slices)
+            .map(|(i, s)| &s[*i])
+            .collect();
+
+        // Odometer style increment, carrying into earlier positions.
+        self.done = true;
+        for i in (self.fixed..self.indices.len()).rev() {
+            self.indices[i] += 1;
+            if self.indices[i] < self.slices[i].len() {
+                self.done = false;
+                break;
+            }
+            self.indices[i] = 0;
+        }
+
+        Some(result)
+    }
+}
+
+pub fn product<'a, T>(slices: &[&'a [T]]) -> Product<'a, T> {
+    Product::new(slices, 0, 0)
+}
+
+// Parallel version of product. Work is split by the element taken from the
+// first slice. With no slices there is a single task, which yields the one
+// empty tuple just like product.
+pub fn par_product<'a, T: Sync>(slices: &[&'a [T]]) -> impl ParallelIterator<Item = Vec<&'a T>> {
+    let slices = slices.to_vec();
+    let first_len = slices.first().map_or(1, |s| s.len());
+
+    (0..first_len)
+        .into_par_iter()
+        .flat_map_iter(move |first| Product::new(&slices, first, 1))
+}
+
+// Apply f to every value, spread across the rayon thread pool if parallel is
+// set. Results are in the same order as the values either way.
+pub fn maybe_par_map<T, R, F>(values: &[T], parallel: bool, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    if parallel {
+        values.par_iter().map(f).collect()
+    } else {
+        values.iter().map(f).collect()
+    }
+}
+
+// A token found by TokenScanner. Positions are byte offsets into the text.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TokenMatch<'a, V> {
+    pub start: usize,
+    pub end: usize,
+    pub value: &'a V,
+}
+
+// Finds every occurrence of a set of tokens in a single pass over the text,
+// including tokens that overlap or sit inside other tokens. Built as an
+// Aho-Corasick automaton: a trie of the tokens, where each state also links to
+// the longest proper suffix of it that is a state, so scanning never backs up.
+pub struct TokenScanner<V> {
+    transitions: Vec<HashMap<u8, usize>>,
+    fail: Vec<usize>,
+    // Tokens that end at each state, including those reached through the
+    // suffix links.
+    outputs: Vec<Vec<usize>>,
+    tokens: Vec<(usize, V)>,
+}
+
+impl<V> TokenScanner<V> {
+    pub fn new<S, I>(tokens: I) -> Self
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = (S, V)>,
+    {
+        let mut scanner = Self {
+            transitions: vec![HashMap::new()],
+            fail: vec![0],
+            outputs: vec![Vec::new()],
+            tokens: Vec::new(),
+        };
+
+        for (token, value) in tokens {
+            scanner.add(token.as_ref(), value);
+        }
+        scanner.link();
+
+        scanner
+    }
+
+    fn add(&mut self, token: &str, value: V) {
+        if token.is_empty() {
+            panic!("Tokens must not be empty");
+        }
+
+        let mut state = 0;
+        for b in token.bytes() {
+            state = match self.transitions[state].get(&b) {
+                Some(next) => *next,
+                None => {
+                    let next = self.transitions.len();
+                    self.transitions.push(HashMap::new());
+                    self.fail.push(0);
+                    self.outputs.push(Vec::new());
+                    self.transitions[state].insert(b, next);
+                    next
+                }
+            };
+        }
+
+        self.outputs[state].push(self.tokens.len());
+        self.tokens.push((token.len(), value));
+    }
+
+    // Set the suffix links breadth first, so every shorter state is linked
+    // before the states below it.
+    fn link(&mut self) {
+        let mut to_visit: VecDeque<_> = self.transitions[0].values().copied().collect();
+
+        while let Some(state) = to_visit.pop_front() {
+            let children: Vec<_> = self.transitions[state]
+                .iter()
+                .map(|(b, s)| (*b, *s))
+                .collect();
+
+            for (b, child) in children {
+                let fail = self.next_state(self.fail[state], b);
+                self.fail[child] = fail;
+
+                let inherited = self.outputs[fail].clone();
+                self.outputs[child].extend(inherited);
+                to_visit.push_back(child);
+            }
+        }
+    }
+
+    fn next_state(&self, mut state: usize, b: u8) -> usize {
+        loop {
+            if let Some(next) = self.transitions[state].get(&b) {
+                return *next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
+    }
+
+    // Every match in the text, ordered by start position and then by length.
+    pub fn find_all(&self, text: &str) -> Vec<TokenMatch<'_, V>> {
+        let mut result = Vec::new();
+        let mut state = 0;
+
+        for (i, b) in text.bytes().enumerate() {
+            state = self.next_state(state, b);
+
+            for t in self.outputs[state].iter() {
+                let (len, value) = &self.tokens[*t];
+                result.push(TokenMatch {
+                    start: i + 1 - len,
+                    end: i + 1,
+                    value,
+                });
+            }
+        }
+
+        result.sort_by_key(|m| (m.start, m.end));
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,4 +496,192 @@ mod tests {
         assert_eq!(combinations[2], (&'a', &'c', &'d'));
         assert_eq!(combinations[3], (&'b', &'c', &'d'));
     }
+
+    #[test]
+    fn test_index_combinations() {
+        let combinations: Vec<_> = index_combinations::<3>(5).collect();
+        assert_eq!(combinations.len(), 10);
+        assert_eq!(combinations[0], [0, 1, 2]);
+        assert_eq!(combinations[1], [0, 1, 3]);
+        assert_eq!(combinations[9], [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_index_combinations_too_few() {
+        assert!(index_combinations::<3>(2).next().is_none());
+        assert_eq!(index_combinations::<0>(2).count(), 1);
+    }
+
+    #[test]
+    fn test_combinations_array_matches_combinations_3() {
+        let values: Vec<_> = "abcde".chars().collect();
+        let expected: Vec<_> = combinations_3(&values).map(|(a, b, c)| [a, b, c]).collect();
+        let result: Vec<_> = combinations_array::<3, _>(&values).collect();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_combinations_k() {
+        let values: Vec<_> = "abcd".chars().collect();
+        let combinations: Vec<_> = combinations_k(&values, 2).collect();
+
+        let expected: Vec<_> = super::combinations(&values)
+            .map(|(a, b)| vec![a, b])
+            .collect();
+        assert_eq!(combinations, expected);
+        assert_eq!(combinations_k(&values, 4).count(), 1);
+        assert_eq!(combinations_k(&values, 5).count(), 0);
+    }
+
+    #[test]
+    fn test_par_combinations() {
+        let values: Vec<_> = (0..12).collect();
+
+        let mut result: Vec<_> = par_combinations_k(&values, 4).collect();
+        result.sort();
+        let expected: Vec<_> = combinations_k(&values, 4).collect();
+        assert_eq!(result, expected);
+
+        let ordered: Vec<_> = par_index_combinations::<4>(12).collect();
+        let expected: Vec<_> = index_combinations::<4>(12).collect();
+        assert_eq!(ordered, expected);
+    }
+
+    #[test]
+    fn test_permutations() {
+        let values: Vec<_> = "abc".chars().collect();
+        let result: Vec<String> = permutations(&values, 2)
+            .map(|p| p.into_iter().collect())
+            .collect();
+
+        assert_eq!(result, vec!["ab", "ac", "ba", "bc", "ca", "cb"]);
+    }
+
+    #[test]
+    fn test_index_permutations() {
+        assert_eq!(index_permutations(4, 4).count(), 24);
+        assert_eq!(index_permutations(5, 3).count(), 60);
+        assert_eq!(index_permutations(3, 0).count(), 1);
+        assert_eq!(index_permutations(2, 3).count(), 0);
+
+        let all: Vec<_> = index_permutations(3, 3).collect();
+        assert_eq!(all[0], vec![0, 1, 2]);
+        assert_eq!(all[1], vec![0, 2, 1]);
+        assert_eq!(all[5], vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_product() {
+        let a = ['a', 'b'];
+        let b = ['x', 'y', 'z'];
+        let result: Vec<String> = product(&[&a[..], &b[..]])
+            .map(|p| p.into_iter().collect())
+            .collect();
+
+        assert_eq!(result, vec!["ax", "ay", "az", "bx", "by", "bz"]);
+    }
+
+    #[test]
+    fn test_product_empty() {
+        let a = ['a', 'b'];
+        let b: [char; 0] = [];
+
+        assert_eq!(product(&[&a[..], &b[..]]).count(), 0);
+        assert_eq!(product::<char>(&[]).count(), 1);
+    }
+
+    #[test]
+    fn test_par_product() {
+        let a = [1, 2, 3];
+        let b = [4, 5];
+        let c = [6, 7, 8, 9];
+        let slices = [&a[..], &b[..], &c[..]];
+
+        let result: Vec<_> = par_product(&slices).collect();
+        let expected: Vec<_> = product(&slices).collect();
+        assert_eq!(result, expected);
+
+        let empty: [&[i32]; 0] = [];
+        let result: Vec<_> = par_product(&empty).collect();
+        assert_eq!(result, vec![Vec::<&i32>::new()]);
+        assert_eq!(result, product(&empty).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_maybe_par_map() {
+        let values: Vec<_> = (0..1000).collect();
+        let sequential = maybe_par_map(&values, false, |v| v * 2);
+        let parallel = maybe_par_map(&values, true, |v| v * 2);
+
+        assert_eq!(sequential, parallel);
+        assert_eq!(parallel[999], 1998);
+    }
+
+    fn scan(scanner: &TokenScanner<u32>, text: &str) -> Vec<(usize, usize, u32)> {
+        scanner
+            .find_all(text)
+            .iter()
+            .map(|m| (m.start, m.end, *m.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_token_scanner_overlapping() {
+        let scanner = TokenScanner::new([("one", 1), ("two", 2), ("eight", 8), ("2", 2)]);
+
+        assert_eq!(
+            scan(&scanner, "eightwone2"),
+            vec![(0, 5, 8), (4, 7, 2), (6, 9, 1), (9, 10, 2)]
+        );
+        assert_eq!(scan(&scanner, "xyz"), vec![]);
+    }
+
+    #[test]
+    fn test_token_scanner_nested() {
+        let scanner = TokenScanner::new([("he", 1), ("she", 2), ("his", 3), ("hers", 4)]);
+
+        assert_eq!(
+            scan(&scanner, "ushers"),
+            vec![(1, 4, 2), (2, 4, 1), (2, 6, 4)]
+        );
+    }
+
+    #[test]
+    fn test_token_scanner_repeated() {
+        let scanner = TokenScanner::new([("aa", 1)]);
+        assert_eq!(
+            scan(&scanner, "aaaa"),
+            vec![(0, 2, 1), (1, 3, 1), (2, 4, 1)]
+        );
+    }
+
+    #[test]
+    fn test_token_scanner_unicode() {
+        let scanner = TokenScanner::new([("eins", 1), ("zwei", 2), ("drei", 3), ("fünf", 5)]);
+        let text = "fünfzweins";
+
+        let matches = scan(&scanner, text);
+        assert_eq!(matches, vec![(0, 5, 5), (5, 9, 2), (7, 11, 1)]);
+        assert_eq!(&text[matches[0].0..matches[0].1], "fünf");
+    }
+
+    #[test]
+    fn test_token_scanner_brute_force() {
+        let tokens = ["ab", "b", "bab", "abab", "ba"];
+        let scanner = TokenScanner::new(tokens.iter().enumerate().map(|(i, t)| (*t, i as u32)));
+        let text = "abababbabba";
+
+        let mut expected = Vec::new();
+        for (i, t) in tokens.iter().enumerate() {
+            for start in 0..text.len() {
+                if text[start..].starts_with(t) {
+                    expected.push((start, start + t.len(), i as u32));
+                }
+            }
+        }
+        expected.sort_by_key(|m| (m.0, m.1));
+
+        assert_eq!(scan(&scanner, text), expected);
+    }
 }